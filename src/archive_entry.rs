@@ -0,0 +1,911 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+
+/// The maximum number of symlinks followed when a path is resolved, like `MAXSYMLINKS` of Linux.
+const MAX_SYMLINK_FOLLOWS: usize = 40;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    File,
+    Directory,
+    Symlink,
+    Hardlink,
+    Other,
+}
+
+/// An entry of an archive file, normalized from the listings of the different backends.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    pub path:            String,
+    pub size:            Option<u64>,
+    pub compressed_size: Option<u64>,
+    /// In the `YYYY-MM-DD HH:MM:SS` form.
+    pub mtime:           Option<String>,
+    pub mode:            Option<String>,
+    #[serde(rename = "type")]
+    pub entry_type:      EntryType,
+    pub link_target:     Option<String>,
+    pub encrypted:       bool,
+}
+
+impl ArchiveEntry {
+    #[inline]
+    pub fn new<S: Into<String>>(path: S, entry_type: EntryType) -> ArchiveEntry {
+        ArchiveEntry {
+            path: path.into(),
+            size: None,
+            compressed_size: None,
+            mtime: None,
+            mode: None,
+            entry_type,
+            link_target: None,
+            encrypted: false,
+        }
+    }
+
+    /// Check whether extracting the entry can write a file outside the destination directory. Return the reason if it can.
+    pub fn get_unsafe_reason(&self) -> Option<&'static str> {
+        let path = self.path.replace('\\', "/");
+
+        if is_absolute(path.as_str()) {
+            return Some("has an absolute path");
+        }
+
+        if path.split('/').any(|component| component == "..") {
+            return Some("has a `..` component");
+        }
+
+        match self.entry_type {
+            EntryType::Symlink => match self.link_target.as_deref() {
+                Some(target) => {
+                    let target = target.replace('\\', "/");
+
+                    // a relative target is resolved from the directory of the symlink
+                    let depth = path.split('/').filter(|c| !c.is_empty() && *c != ".").count();
+
+                    if is_absolute(target.as_str()) || escapes(depth.saturating_sub(1), &target) {
+                        Some("is a symlink pointing outside the destination")
+                    } else {
+                        None
+                    }
+                },
+                None => Some("is a symlink whose target is unknown"),
+            },
+            EntryType::Hardlink => match self.link_target.as_deref() {
+                Some(target) => {
+                    let target = target.replace('\\', "/");
+
+                    // the target of a hardlink is a path in the archive
+                    if is_absolute(target.as_str()) || escapes(0, &target) {
+                        Some("is a hardlink to a file outside the destination")
+                    } else {
+                        None
+                    }
+                },
+                None => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Check all entries of an archive file in order, and return the paths of the unsafe ones with the reasons. Besides the checks of `get_unsafe_reason`, the paths and the link targets are resolved through the symlinks which the earlier entries create, so that a chain like `x -> .`, `x/y -> ..` and `x/y/z` is found.
+    pub fn find_unsafe_entries(entries: &[ArchiveEntry]) -> Vec<(String, &'static str)> {
+        let mut checker = UnsafeEntryChecker::default();
+
+        entries
+            .iter()
+            .filter_map(|entry| checker.check(entry).map(|reason| (entry.path.clone(), reason)))
+            .collect()
+    }
+
+    /// Parse the output of `tar -t -v --full-time`.
+    pub fn parse_tar_listing(listing: &str) -> Vec<ArchiveEntry> {
+        let mut entries = Vec::new();
+
+        for line in listing.lines() {
+            let (fields, name) = match split_fields(line, 5) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let mode = fields[0];
+
+            let entry_type = match get_entry_type_from_mode(mode) {
+                Some(entry_type) => entry_type,
+                None => continue,
+            };
+
+            let (name, link_target) = match entry_type {
+                EntryType::Symlink => match name.split_once(" -> ") {
+                    Some((name, target)) => (name, Some(unescape_tar_name(target))),
+                    None => (name, None),
+                },
+                EntryType::Hardlink => match name.split_once(" link to ") {
+                    Some((name, target)) => (name, Some(unescape_tar_name(target))),
+                    None => (name, None),
+                },
+                _ => (name, None),
+            };
+
+            let mut entry = ArchiveEntry::new(normalize_path(unescape_tar_name(name)), entry_type);
+
+            entry.size = fields[2].parse().ok();
+            entry.mtime = Some(format!("{} {}", fields[3], fields[4]));
+            entry.mode = Some(mode.to_string());
+            entry.link_target = link_target;
+
+            entries.push(entry);
+        }
+
+        entries
+    }
+
+    /// Parse the output of `7z l -slt`.
+    pub fn parse_7z_listing(listing: &str) -> Vec<ArchiveEntry> {
+        let mut entries = Vec::new();
+
+        let mut lines = listing.lines();
+
+        // skip the information of the archive file itself
+        for line in lines.by_ref() {
+            if line == "----------" {
+                break;
+            }
+        }
+
+        let mut entry: Option<ArchiveEntry> = None;
+
+        for line in lines {
+            let (key, value) = match line.split_once(" = ") {
+                Some(v) => v,
+                None => {
+                    if line.is_empty() {
+                        entries.extend(entry.take());
+                    }
+
+                    continue;
+                },
+            };
+
+            if key == "Path" {
+                entries.extend(entry.take());
+
+                entry = Some(ArchiveEntry::new(normalize_path(value.to_string()), EntryType::File));
+
+                continue;
+            }
+
+            let entry = match entry.as_mut() {
+                Some(entry) => entry,
+                None => continue,
+            };
+
+            match key {
+                "Folder" if value == "+" => entry.entry_type = EntryType::Directory,
+                "Size" => entry.size = value.parse().ok(),
+                "Packed Size" => entry.compressed_size = value.parse().ok(),
+                "Modified" => entry.mtime = value.get(..19).map(|s| s.to_string()),
+                "Attributes" => {
+                    // e.g. `A_ -rw-r--r--`, `D_ drwxr-xr-x` or `A`
+                    if let Some(mode) = value.split_whitespace().last() {
+                        if let Some(entry_type) = get_entry_type_from_mode(mode) {
+                            if entry.entry_type != EntryType::Directory {
+                                entry.entry_type = entry_type;
+                            }
+
+                            entry.mode = Some(mode.to_string());
+                        }
+                    }
+                },
+                "Encrypted" => entry.encrypted = value == "+",
+                "Symbolic Link" if !value.is_empty() => {
+                    entry.entry_type = EntryType::Symlink;
+                    entry.link_target = Some(value.to_string());
+                },
+                "Hard Link" if !value.is_empty() => {
+                    entry.entry_type = EntryType::Hardlink;
+                    entry.link_target = Some(value.to_string());
+                },
+                _ => (),
+            }
+        }
+
+        entries.extend(entry);
+
+        entries
+    }
+
+    /// Parse the output of `unzip -Z -l -T`.
+    pub fn parse_zipinfo_listing(listing: &str) -> Vec<ArchiveEntry> {
+        let mut entries = Vec::new();
+
+        for line in listing.lines() {
+            // e.g. `-rw-r--r--  3.0 unx        6 TX       18 stor 20231018.024448 src/a.txt`
+            let (fields, name) = match split_fields(line, 8) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            // the second field is the version of the zip software, like `3.0`
+            if fields[1].parse::<f32>().is_err() {
+                continue;
+            }
+
+            let size = match fields[3].parse() {
+                Ok(size) => size,
+                Err(_) => continue,
+            };
+
+            let mode = fields[0];
+
+            let entry_type = if name.ends_with('/') {
+                EntryType::Directory
+            } else {
+                get_entry_type_from_mode(mode).unwrap_or(EntryType::File)
+            };
+
+            let mut entry = ArchiveEntry::new(normalize_path(name.to_string()), entry_type);
+
+            entry.size = Some(size);
+            entry.compressed_size = fields[5].parse().ok();
+            entry.mtime = parse_zipinfo_time(fields[7]);
+            entry.mode = Some(mode.to_string());
+            // an uppercase letter means the entry is encrypted
+            entry.encrypted = fields[4].starts_with(|c: char| c.is_ascii_uppercase());
+
+            entries.push(entry);
+        }
+
+        entries
+    }
+
+    /// Parse the output of `unrar lt`.
+    pub fn parse_unrar_listing(listing: &str) -> Vec<ArchiveEntry> {
+        let mut entries = Vec::new();
+
+        let mut entry: Option<ArchiveEntry> = None;
+
+        for line in listing.lines() {
+            let (key, value) = match line.trim_start().split_once(": ") {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if key == "Name" {
+                entries.extend(entry.take());
+
+                entry = Some(ArchiveEntry::new(normalize_path(value.to_string()), EntryType::File));
+
+                continue;
+            }
+
+            let entry = match entry.as_mut() {
+                Some(entry) => entry,
+                None => continue,
+            };
+
+            match key {
+                "Type" => {
+                    entry.entry_type = match value {
+                        "File" => EntryType::File,
+                        "Directory" => EntryType::Directory,
+                        "Hard link" => EntryType::Hardlink,
+                        _ if value.contains("link") => EntryType::Symlink,
+                        _ => EntryType::Other,
+                    };
+                },
+                "Target" => entry.link_target = Some(value.to_string()),
+                "Size" => entry.size = value.parse().ok(),
+                "Packed size" => entry.compressed_size = value.parse().ok(),
+                "mtime" => entry.mtime = value.get(..19).map(|s| s.to_string()),
+                "Attributes" if get_entry_type_from_mode(value).is_some() => {
+                    entry.mode = Some(value.to_string());
+                },
+                "Flags" => entry.encrypted = value.contains("encrypted"),
+                _ => (),
+            }
+        }
+
+        entries.extend(entry);
+
+        entries
+    }
+}
+
+/// Split the first `n` whitespace-separated fields from a line, and return them with the rest of the line.
+fn split_fields(line: &str, n: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(n);
+
+    let mut rest = line.trim_start();
+
+    for _ in 0..n {
+        let end = rest.find(char::is_whitespace)?;
+
+        fields.push(&rest[..end]);
+
+        rest = rest[end..].trim_start();
+    }
+
+    if rest.is_empty() {
+        None
+    } else {
+        Some((fields, rest))
+    }
+}
+
+/// Get the entry type from a Unix mode string like `drwxr-xr-x`.
+fn get_entry_type_from_mode(mode: &str) -> Option<EntryType> {
+    if mode.len() != 10 {
+        return None;
+    }
+
+    match mode.as_bytes()[0] {
+        b'-' => Some(EntryType::File),
+        b'd' => Some(EntryType::Directory),
+        b'l' => Some(EntryType::Symlink),
+        b'h' => Some(EntryType::Hardlink),
+        b'b' | b'c' | b'p' | b's' => Some(EntryType::Other),
+        _ => None,
+    }
+}
+
+/// Check the entries of an archive in order, following the symlinks which the earlier entries create. See `ArchiveEntry::find_unsafe_entries`.
+#[derive(Debug, Default)]
+pub(crate) struct UnsafeEntryChecker {
+    /// The resolved paths of the symlinks created so far, and their targets.
+    symlinks: HashMap<Vec<String>, String>,
+}
+
+impl UnsafeEntryChecker {
+    /// Check the next entry. Return the reason if it is unsafe.
+    pub(crate) fn check(&mut self, entry: &ArchiveEntry) -> Option<&'static str> {
+        if let Some(reason) = entry.get_unsafe_reason() {
+            return Some(reason);
+        }
+
+        let path = entry.path.replace('\\', "/");
+
+        // the last component is replaced by the entry rather than followed
+        let resolved_path = match resolve_path(&self.symlinks, Vec::new(), path.as_str(), false) {
+            Some(resolved_path) => resolved_path,
+            None => return Some("is extracted through a symlink outside the destination"),
+        };
+
+        match entry.entry_type {
+            EntryType::Symlink => {
+                // checked by `get_unsafe_reason`
+                let target = entry.link_target.as_deref().unwrap().replace('\\', "/");
+
+                let mut directory = resolved_path.clone();
+
+                directory.pop();
+
+                if resolve_path(&self.symlinks, directory, target.as_str(), true).is_none() {
+                    return Some(
+                        "is a symlink pointing outside the destination through other symlinks",
+                    );
+                }
+
+                self.symlinks.insert(resolved_path, target);
+            },
+            EntryType::Hardlink => {
+                if let Some(target) = entry.link_target.as_deref() {
+                    let target = target.replace('\\', "/");
+
+                    if resolve_path(&self.symlinks, Vec::new(), target.as_str(), false).is_none() {
+                        return Some(
+                            "is a hardlink to a file outside the destination through symlinks",
+                        );
+                    }
+                }
+
+                self.symlinks.remove(&resolved_path);
+            },
+            _ => {
+                self.symlinks.remove(&resolved_path);
+            },
+        }
+
+        None
+    }
+}
+
+/// A path like `/foo` or `C:/foo`.
+#[inline]
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+
+    path.starts_with('/')
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Whether a relative path goes above the destination directory, when it is resolved from a directory at the depth.
+fn escapes(mut depth: usize, path: &str) -> bool {
+    for component in path.split('/') {
+        match component {
+            "" | "." => (),
+            ".." => {
+                if depth == 0 {
+                    return true;
+                }
+
+                depth -= 1;
+            },
+            _ => depth += 1,
+        }
+    }
+
+    false
+}
+
+/// Resolve a relative path from a directory in the destination, following the known symlinks. Return the components of the resolved path, or `None` if it goes above the destination directory or too many symlinks are followed.
+fn resolve_path(
+    symlinks: &HashMap<Vec<String>, String>,
+    mut resolved: Vec<String>,
+    path: &str,
+    follow_last: bool,
+) -> Option<Vec<String>> {
+    let mut components: VecDeque<String> = path.split('/').map(String::from).collect();
+
+    let mut follows = 0;
+
+    while let Some(component) = components.pop_front() {
+        match component.as_str() {
+            "" | "." => continue,
+            ".." => {
+                resolved.pop()?;
+
+                continue;
+            },
+            _ => resolved.push(component),
+        }
+
+        let is_last = components.iter().all(|component| component.is_empty() || component == ".");
+
+        if is_last && !follow_last {
+            continue;
+        }
+
+        if let Some(target) = symlinks.get(&resolved) {
+            follows += 1;
+
+            if follows > MAX_SYMLINK_FOLLOWS || is_absolute(target.as_str()) {
+                return None;
+            }
+
+            resolved.pop();
+
+            for component in target.split('/').rev() {
+                components.push_front(String::from(component));
+            }
+        }
+    }
+
+    Some(resolved)
+}
+
+#[inline]
+fn normalize_path(mut path: String) -> String {
+    while path.len() > 1 && path.ends_with('/') {
+        path.pop();
+    }
+
+    path
+}
+
+/// Convert `20231018.024448` to `2023-10-18 02:44:48`.
+fn parse_zipinfo_time(s: &str) -> Option<String> {
+    let (date, time) = s.split_once('.')?;
+
+    if date.len() != 8 || time.len() != 6 {
+        return None;
+    }
+
+    Some(format!(
+        "{}-{}-{} {}:{}:{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    ))
+}
+
+/// GNU tar escapes backslashes and non-printable characters in its listing.
+fn unescape_tar_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+
+    let mut output = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'\\' || i + 1 == bytes.len() {
+            output.push(bytes[i]);
+            i += 1;
+
+            continue;
+        }
+
+        let c = bytes[i + 1];
+
+        match c {
+            b'0'..=b'7' => {
+                let digits =
+                    bytes[i + 1..].iter().take(3).take_while(|b| (b'0'..=b'7').contains(b)).count();
+
+                let value = bytes[i + 1..i + 1 + digits]
+                    .iter()
+                    .fold(0u32, |value, b| value * 8 + (b - b'0') as u32);
+
+                output.push(value as u8);
+                i += 1 + digits;
+
+                continue;
+            },
+            b'n' => output.push(b'\n'),
+            b't' => output.push(b'\t'),
+            b'r' => output.push(b'\r'),
+            b'a' => output.push(0x07),
+            b'b' => output.push(0x08),
+            b'f' => output.push(0x0C),
+            b'v' => output.push(0x0B),
+            b'?' => output.push(0x7F),
+            _ => output.push(c),
+        }
+
+        i += 2;
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_link(path: &str, entry_type: EntryType, target: &str) -> ArchiveEntry {
+        let mut entry = ArchiveEntry::new(path, entry_type);
+
+        entry.link_target = Some(target.to_string());
+
+        entry
+    }
+
+    #[test]
+    fn unsafe_reason() {
+        assert_eq!(None, ArchiveEntry::new("a/b.txt", EntryType::File).get_unsafe_reason());
+        assert_eq!(None, ArchiveEntry::new("./a/..b", EntryType::File).get_unsafe_reason());
+        assert_eq!(
+            Some("has an absolute path"),
+            ArchiveEntry::new("/etc/passwd", EntryType::File).get_unsafe_reason()
+        );
+        assert_eq!(
+            Some("has an absolute path"),
+            ArchiveEntry::new("C:\\Windows", EntryType::File).get_unsafe_reason()
+        );
+        assert_eq!(
+            Some("has a `..` component"),
+            ArchiveEntry::new("a/../../b", EntryType::File).get_unsafe_reason()
+        );
+        assert_eq!(
+            Some("has a `..` component"),
+            ArchiveEntry::new("a\\..\\b", EntryType::File).get_unsafe_reason()
+        );
+
+        assert_eq!(None, create_link("a/b/link", EntryType::Symlink, "../c").get_unsafe_reason());
+        assert_eq!(
+            Some("is a symlink pointing outside the destination"),
+            create_link("a/link", EntryType::Symlink, "../../c").get_unsafe_reason()
+        );
+        assert_eq!(
+            Some("is a symlink pointing outside the destination"),
+            create_link("link", EntryType::Symlink, "/etc").get_unsafe_reason()
+        );
+        assert_eq!(
+            Some("is a symlink whose target is unknown"),
+            ArchiveEntry::new("link", EntryType::Symlink).get_unsafe_reason()
+        );
+
+        // the target of a hardlink is resolved from the destination rather than its directory
+        assert_eq!(None, create_link("a/b/hard", EntryType::Hardlink, "a/c").get_unsafe_reason());
+        assert_eq!(
+            Some("is a hardlink to a file outside the destination"),
+            create_link("a/b/hard", EntryType::Hardlink, "../c").get_unsafe_reason()
+        );
+        assert_eq!(None, ArchiveEntry::new("hard", EntryType::Hardlink).get_unsafe_reason());
+    }
+
+    #[test]
+    fn escape_depth() {
+        assert!(!escapes(0, "a/b"));
+        assert!(!escapes(0, "a/../b"));
+        assert!(!escapes(1, ".."));
+        assert!(!escapes(0, "./a//./"));
+        assert!(escapes(0, ".."));
+        assert!(escapes(1, "../.."));
+        assert!(escapes(0, "a/../../b"));
+    }
+
+    #[test]
+    fn unsafe_entries_through_symlinks() {
+        let entries = [
+            create_link("x", EntryType::Symlink, "."),
+            create_link("x/y", EntryType::Symlink, ".."),
+            ArchiveEntry::new("x/y/escaped.txt", EntryType::File),
+        ];
+
+        assert_eq!(
+            vec![(
+                String::from("x/y"),
+                "is a symlink pointing outside the destination through other symlinks"
+            )],
+            ArchiveEntry::find_unsafe_entries(&entries)
+        );
+
+        // `a/link` is safe until `b` becomes a symlink
+        let entries = [
+            create_link("a/link", EntryType::Symlink, "../b/.."),
+            create_link("b", EntryType::Symlink, "c/.."),
+            ArchiveEntry::new("a/link/escaped.txt", EntryType::File),
+            create_link("hard", EntryType::Hardlink, "a/link/passwd"),
+        ];
+
+        assert_eq!(
+            vec![
+                (
+                    String::from("a/link/escaped.txt"),
+                    "is extracted through a symlink outside the destination"
+                ),
+                (
+                    String::from("hard"),
+                    "is a hardlink to a file outside the destination through symlinks"
+                ),
+            ],
+            ArchiveEntry::find_unsafe_entries(&entries)
+        );
+
+        // a symlink replaced by a directory is not followed anymore
+        let entries = [
+            create_link("a", EntryType::Symlink, "b"),
+            ArchiveEntry::new("a", EntryType::Directory),
+            ArchiveEntry::new("a/c.txt", EntryType::File),
+            create_link("d/e", EntryType::Symlink, "../a/c.txt"),
+        ];
+
+        assert!(ArchiveEntry::find_unsafe_entries(&entries).is_empty());
+
+        // a loop of symlinks
+        let entries = [
+            create_link("a", EntryType::Symlink, "b"),
+            create_link("b", EntryType::Symlink, "a"),
+            ArchiveEntry::new("a/c.txt", EntryType::File),
+        ];
+
+        assert_eq!(
+            vec![(
+                String::from("a/c.txt"),
+                "is extracted through a symlink outside the destination"
+            )],
+            ArchiveEntry::find_unsafe_entries(&entries)
+        );
+    }
+
+    #[test]
+    fn tar_listing() {
+        let listing = "drwxr-xr-x root/root         0 2023-10-18 04:09:51 d/
+-rw-r--r-- root/root         1 2023-10-18 04:09:51 d/sp ace\\\\b.txt
+lrwxrwxrwx root/root         0 2023-10-18 04:09:51 d/link -> a.txt
+-rw-r--r-- root/root         5 2023-10-18 04:09:51 d/hard
+hrw-r--r-- root/root         0 2023-10-18 04:09:51 d/a.txt link to d/hard
+";
+
+        let entries = ArchiveEntry::parse_tar_listing(listing);
+
+        assert_eq!(5, entries.len());
+
+        assert_eq!("d", entries[0].path);
+        assert_eq!(EntryType::Directory, entries[0].entry_type);
+
+        assert_eq!("d/sp ace\\b.txt", entries[1].path);
+        assert_eq!(EntryType::File, entries[1].entry_type);
+        assert_eq!(Some(1), entries[1].size);
+        assert_eq!(Some("2023-10-18 04:09:51"), entries[1].mtime.as_deref());
+        assert_eq!(Some("-rw-r--r--"), entries[1].mode.as_deref());
+
+        assert_eq!("d/link", entries[2].path);
+        assert_eq!(EntryType::Symlink, entries[2].entry_type);
+        assert_eq!(Some("a.txt"), entries[2].link_target.as_deref());
+
+        assert_eq!("d/a.txt", entries[4].path);
+        assert_eq!(EntryType::Hardlink, entries[4].entry_type);
+        assert_eq!(Some("d/hard"), entries[4].link_target.as_deref());
+    }
+
+    #[test]
+    fn p7z_listing() {
+        let listing = "7-Zip (a) 23.01 (x64) : Copyright (c) 1999-2023 Igor Pavlov : 2023-06-20
+
+Listing archive: x.7z
+
+--
+Path = x.7z
+Type = 7z
+Physical Size = 330
+Headers Size = 250
+Method = LZMA2:12 7zAES
+Solid = +
+Blocks = 1
+
+----------
+Path = d
+Size = 0
+Packed Size = 0
+Modified = 2023-10-18 04:09:51.1234567
+Attributes = D_ drwxr-xr-x
+CRC = 
+Encrypted = -
+Method = 
+Block = 
+
+Path = d/a.txt
+Size = 5
+Packed Size = 16
+Modified = 2023-10-18 04:09:51.1234567
+Attributes = A_ -rw-r--r--
+CRC = 3610A686
+Encrypted = +
+Method = LZMA2:12 7zAES:19
+Block = 0
+
+Path = d/link
+Size = 5
+Packed Size = 
+Modified = 2023-10-18 04:09:51.1234567
+Attributes = A_ lrwxrwxrwx
+Symbolic Link = a.txt
+
+Path = e
+Size = 0
+Folder = +
+Attributes = D
+";
+
+        let entries = ArchiveEntry::parse_7z_listing(listing);
+
+        assert_eq!(4, entries.len());
+
+        assert_eq!("d", entries[0].path);
+        assert_eq!(EntryType::Directory, entries[0].entry_type);
+        assert_eq!(Some("drwxr-xr-x"), entries[0].mode.as_deref());
+
+        assert_eq!("d/a.txt", entries[1].path);
+        assert_eq!(EntryType::File, entries[1].entry_type);
+        assert_eq!(Some(5), entries[1].size);
+        assert_eq!(Some(16), entries[1].compressed_size);
+        assert_eq!(Some("2023-10-18 04:09:51"), entries[1].mtime.as_deref());
+        assert!(entries[1].encrypted);
+
+        assert_eq!(EntryType::Symlink, entries[2].entry_type);
+        assert_eq!(Some("a.txt"), entries[2].link_target.as_deref());
+        assert_eq!(None, entries[2].compressed_size);
+
+        assert_eq!("e", entries[3].path);
+        assert_eq!(EntryType::Directory, entries[3].entry_type);
+        assert_eq!(None, entries[3].mode);
+    }
+
+    #[test]
+    fn zipinfo_listing() {
+        let listing = "Archive:  x.zip
+Zip file size: 750 bytes, number of entries: 4
+drwxr-xr-x  3.0 unx        0 bx        0 stor 20231018.040951 d/
+-rw-r--r--  3.0 unx        2 TX       14 stor 20231018.040951 d/sp ace.txt
+lrwxrwxrwx  3.0 unx        5 bx        5 stor 20231018.040951 d/link
+-rw-a--     2.0 fat     1234 bx      567 defN 20231018.040951 d/b.txt
+4 files, 1241 bytes uncompressed, 586 bytes compressed:  52.8%
+";
+
+        let entries = ArchiveEntry::parse_zipinfo_listing(listing);
+
+        assert_eq!(4, entries.len());
+
+        assert_eq!("d", entries[0].path);
+        assert_eq!(EntryType::Directory, entries[0].entry_type);
+
+        assert_eq!("d/sp ace.txt", entries[1].path);
+        assert_eq!(EntryType::File, entries[1].entry_type);
+        assert_eq!(Some(2), entries[1].size);
+        assert_eq!(Some(14), entries[1].compressed_size);
+        assert_eq!(Some("2023-10-18 04:09:51"), entries[1].mtime.as_deref());
+        assert!(entries[1].encrypted);
+
+        // the targets are not in the listing
+        assert_eq!(EntryType::Symlink, entries[2].entry_type);
+        assert_eq!(None, entries[2].link_target);
+        assert!(!entries[2].encrypted);
+
+        // a mode of DOS attributes
+        assert_eq!(EntryType::File, entries[3].entry_type);
+        assert_eq!(Some(1234), entries[3].size);
+    }
+
+    #[test]
+    fn unrar_listing() {
+        let listing = "UNRAR 6.24 freeware      Copyright (c) 1993-2023 Alexander Roshal
+
+Archive: x.rar
+Details: RAR 5
+
+        Name: d/a.txt
+        Type: File
+        Size: 5
+ Packed size: 16
+       Ratio: 320%
+       mtime: 2023-10-18 04:09:51,123456789
+  Attributes: -rw-r--r--
+       CRC32: 3610A686
+     Host OS: Unix
+ Compression: RAR 5.0(v50) -m3 -md=128K
+       Flags: encrypted
+
+        Name: d/link
+        Type: Unix symbolic link
+      Target: a.txt
+        Size: 5
+  Attributes: lrwxrwxrwx
+
+        Name: d/hard
+        Type: Hard link
+      Target: d/a.txt
+
+        Name: d
+        Type: Directory
+  Attributes: drwxr-xr-x
+
+        Name: e
+        Type: Directory
+  Attributes: ..D....
+";
+
+        let entries = ArchiveEntry::parse_unrar_listing(listing);
+
+        assert_eq!(5, entries.len());
+
+        assert_eq!("d/a.txt", entries[0].path);
+        assert_eq!(EntryType::File, entries[0].entry_type);
+        assert_eq!(Some(5), entries[0].size);
+        assert_eq!(Some(16), entries[0].compressed_size);
+        assert_eq!(Some("2023-10-18 04:09:51"), entries[0].mtime.as_deref());
+        assert_eq!(Some("-rw-r--r--"), entries[0].mode.as_deref());
+        assert!(entries[0].encrypted);
+
+        assert_eq!(EntryType::Symlink, entries[1].entry_type);
+        assert_eq!(Some("a.txt"), entries[1].link_target.as_deref());
+        assert!(!entries[1].encrypted);
+
+        assert_eq!(EntryType::Hardlink, entries[2].entry_type);
+        assert_eq!(Some("d/a.txt"), entries[2].link_target.as_deref());
+
+        assert_eq!(EntryType::Directory, entries[3].entry_type);
+
+        // Windows attributes are not a mode
+        assert_eq!(EntryType::Directory, entries[4].entry_type);
+        assert_eq!(None, entries[4].mode);
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!("a b.txt", unescape_tar_name("a b.txt"));
+        assert_eq!("a\\b", unescape_tar_name("a\\\\b"));
+        assert_eq!("a\nb\tc", unescape_tar_name("a\\nb\\tc"));
+        assert_eq!("é", unescape_tar_name("\\303\\251"));
+        assert_eq!("\u{1}2", unescape_tar_name("\\0012"));
+        assert_eq!("\u{7F}", unescape_tar_name("\\?"));
+        // a trailing backslash is kept
+        assert_eq!("a\\", unescape_tar_name("a\\"));
+    }
+}
@@ -1,8 +1,22 @@
-use std::path::Path;
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io,
+    io::Read,
+    path::Path,
+    process::Stdio,
+    str::FromStr,
+};
 
-use anyhow::anyhow;
+use execute::command_args;
 
-#[derive(Debug)]
+use crate::{
+    command::create_input_pipe, volume::FIRST_VOLUME_EXTENSION, ExecutablePaths, XcompressError,
+};
+
+const HEADER_SIZE: usize = 512;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum ArchiveFormat {
     Z,
     Zip,
@@ -26,15 +40,55 @@ pub enum ArchiveFormat {
 }
 
 impl ArchiveFormat {
+    pub const NAMES: &'static str = "Z, zip, gz, bz2, lz, xz, lzma, 7z, tar, tar.Z, tar.gz, \
+                                     tar.bz2, tar.lz, tar.xz, tar.lzma, tar.7z, tar.zst, rar, zst \
+                                     (aliases: compress, gzip, bzip2, lzip, zstd, 7zip, taz, tgz, \
+                                     tbz2, tbz, txz, tlz, t7z, tzst)";
+
+    /// The name of this format, which is also the file name extension.
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        match self {
+            ArchiveFormat::Z => "Z",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Gzip => "gz",
+            ArchiveFormat::Bzip2 => "bz2",
+            ArchiveFormat::Lz => "lz",
+            ArchiveFormat::Xz => "xz",
+            ArchiveFormat::Lzma => "lzma",
+            ArchiveFormat::P7z => "7z",
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::TarZ => "tar.Z",
+            ArchiveFormat::TarGzip => "tar.gz",
+            ArchiveFormat::TarBzip2 => "tar.bz2",
+            ArchiveFormat::TarLz => "tar.lz",
+            ArchiveFormat::TarXz => "tar.xz",
+            ArchiveFormat::TarLzma => "tar.lzma",
+            ArchiveFormat::Tar7z => "tar.7z",
+            ArchiveFormat::TarZstd => "tar.zst",
+            ArchiveFormat::Rar => "rar",
+            ArchiveFormat::Zstd => "zst",
+        }
+    }
+
     pub fn get_archive_format_from_file_path<P: AsRef<Path>>(
         file_path: P,
-    ) -> anyhow::Result<ArchiveFormat> {
+    ) -> Result<ArchiveFormat, XcompressError> {
         let file_path = file_path.as_ref();
 
         if let Some(file_name) = file_path.file_name() {
             if let Some(file_name) = file_name.to_str() {
                 let file_name = file_name.to_ascii_lowercase();
 
+                // the first volume of a split archive, like foo.tar.zst.001
+                let file_name = match file_name
+                    .strip_suffix(FIRST_VOLUME_EXTENSION)
+                    .and_then(|file_name| file_name.strip_suffix('.'))
+                {
+                    Some(file_name) => file_name,
+                    None => file_name.as_str(),
+                };
+
                 if file_name.ends_with("tar.z") {
                     return Ok(ArchiveFormat::TarZ);
                 } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
@@ -47,10 +101,7 @@ impl ArchiveFormat {
                     return Ok(ArchiveFormat::TarXz);
                 } else if file_name.ends_with(".tar.lzma") || file_name.ends_with(".tlz") {
                     return Ok(ArchiveFormat::TarLzma);
-                } else if file_name.ends_with(".tar.7z")
-                    || file_name.ends_with(".tar.7z.001")
-                    || file_name.ends_with(".t7z")
-                {
+                } else if file_name.ends_with(".tar.7z") || file_name.ends_with(".t7z") {
                     return Ok(ArchiveFormat::Tar7z);
                 } else if file_name.ends_with(".tar.zst") {
                     return Ok(ArchiveFormat::TarZstd);
@@ -70,7 +121,7 @@ impl ArchiveFormat {
                     return Ok(ArchiveFormat::Xz);
                 } else if file_name.ends_with(".lzma") {
                     return Ok(ArchiveFormat::Lzma);
-                } else if file_name.ends_with(".7z") || file_name.ends_with(".7z.001") {
+                } else if file_name.ends_with(".7z") {
                     return Ok(ArchiveFormat::P7z);
                 } else if file_name.ends_with(".rar") {
                     return Ok(ArchiveFormat::Rar);
@@ -80,6 +131,364 @@ impl ArchiveFormat {
             }
         }
 
-        Err(anyhow!("Unknown archive format."))
+        Err(XcompressError::UnknownFormat)
+    }
+
+    /// Recognize the format of an existing file by its content. Fall back to the file name extension if the content is ambiguous.
+    pub fn get_archive_format_from_file<P: AsRef<Path>>(
+        file_path: P,
+        executable_paths: &ExecutablePaths,
+    ) -> Result<ArchiveFormat, XcompressError> {
+        let file_path = file_path.as_ref();
+
+        let format_from_file_path = Self::get_archive_format_from_file_path(file_path);
+
+        let header = {
+            let mut file = File::open(file_path)?;
+
+            let mut buffer = [0u8; HEADER_SIZE];
+
+            let length = read_fully(&mut file, &mut buffer)?;
+
+            buffer[..length].to_vec()
+        };
+
+        let format = match Self::get_archive_format_from_magic_bytes(&header) {
+            Some(format) => format,
+            None => return format_from_file_path,
+        };
+
+        match format {
+            ArchiveFormat::Z
+            | ArchiveFormat::Gzip
+            | ArchiveFormat::Bzip2
+            | ArchiveFormat::Lz
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Zstd => {
+                let tar_format = format.to_tar_format().unwrap();
+
+                let input = File::open(file_path)?.into();
+
+                match peek_tar_in_compressed_stream(input, format, executable_paths) {
+                    Some(true) => Ok(tar_format),
+                    Some(false) => Ok(format),
+                    None => match format_from_file_path {
+                        Ok(format_from_file_path) if format_from_file_path == tar_format => {
+                            Ok(tar_format)
+                        },
+                        _ => Ok(format),
+                    },
+                }
+            },
+            ArchiveFormat::P7z => match format_from_file_path {
+                // the tar file inside a 7z archive cannot be peeked without the password
+                Ok(ArchiveFormat::Tar7z) => Ok(ArchiveFormat::Tar7z),
+                _ => Ok(ArchiveFormat::P7z),
+            },
+            _ => Ok(format),
+        }
+    }
+
+    /// Recognize the format of a stream by its beginning, like the data read from stdin. A compressed tar archive is only recognized if the beginning is long enough to be decompressed into a tar header.
+    pub fn get_archive_format_from_header(
+        header: &[u8],
+        executable_paths: &ExecutablePaths,
+    ) -> Result<ArchiveFormat, XcompressError> {
+        let format = match Self::get_archive_format_from_magic_bytes(header) {
+            Some(format) => format,
+            None => return Err(XcompressError::UnknownFormat),
+        };
+
+        match format.to_tar_format() {
+            Some(tar_format) if format != ArchiveFormat::P7z => {
+                let input = create_input_pipe(io::Cursor::new(header.to_vec()))?;
+
+                match peek_tar_in_compressed_stream(input, format, executable_paths) {
+                    Some(true) => Ok(tar_format),
+                    _ => Ok(format),
+                }
+            },
+            _ => Ok(format),
+        }
+    }
+
+    /// Compressed streams are recognized as single-file formats, e.g. `Gzip` rather than `TarGzip`.
+    pub fn get_archive_format_from_magic_bytes(header: &[u8]) -> Option<ArchiveFormat> {
+        if header.starts_with(&[0x1F, 0x8B]) {
+            Some(ArchiveFormat::Gzip)
+        } else if header.starts_with(&[0x1F, 0x9D]) {
+            Some(ArchiveFormat::Z)
+        } else if header.starts_with(b"BZh") {
+            Some(ArchiveFormat::Bzip2)
+        } else if header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveFormat::Xz)
+        } else if header.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(ArchiveFormat::Zstd)
+        } else if header.starts_with(b"LZIP") {
+            Some(ArchiveFormat::Lz)
+        } else if header.starts_with(&[b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C]) {
+            Some(ArchiveFormat::P7z)
+        } else if header.starts_with(b"Rar!\x1A\x07\x00")
+            || header.starts_with(b"Rar!\x1A\x07\x01\x00")
+        {
+            Some(ArchiveFormat::Rar)
+        } else if header.starts_with(b"PK\x03\x04")
+            || header.starts_with(b"PK\x05\x06")
+            || header.starts_with(b"PK\x07\x08")
+        {
+            Some(ArchiveFormat::Zip)
+        } else if is_tar_header(header) {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Get the tar format which uses this format as its compressor.
+    pub fn to_tar_format(self) -> Option<ArchiveFormat> {
+        match self {
+            ArchiveFormat::Z => Some(ArchiveFormat::TarZ),
+            ArchiveFormat::Gzip => Some(ArchiveFormat::TarGzip),
+            ArchiveFormat::Bzip2 => Some(ArchiveFormat::TarBzip2),
+            ArchiveFormat::Lz => Some(ArchiveFormat::TarLz),
+            ArchiveFormat::Xz => Some(ArchiveFormat::TarXz),
+            ArchiveFormat::Lzma => Some(ArchiveFormat::TarLzma),
+            ArchiveFormat::P7z => Some(ArchiveFormat::Tar7z),
+            ArchiveFormat::Zstd => Some(ArchiveFormat::TarZstd),
+            _ => None,
+        }
+    }
+
+    /// Get the format of the compressor which is applied to the tar stream. The inverse of `to_tar_format`.
+    pub fn to_compressor_format(self) -> Option<ArchiveFormat> {
+        match self {
+            ArchiveFormat::TarZ => Some(ArchiveFormat::Z),
+            ArchiveFormat::TarGzip => Some(ArchiveFormat::Gzip),
+            ArchiveFormat::TarBzip2 => Some(ArchiveFormat::Bzip2),
+            ArchiveFormat::TarLz => Some(ArchiveFormat::Lz),
+            ArchiveFormat::TarXz => Some(ArchiveFormat::Xz),
+            ArchiveFormat::TarLzma => Some(ArchiveFormat::Lzma),
+            ArchiveFormat::Tar7z => Some(ArchiveFormat::P7z),
+            ArchiveFormat::TarZstd => Some(ArchiveFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Whether the archive files of this format can be encrypted with a password.
+    #[inline]
+    pub const fn supports_password(self) -> bool {
+        matches!(
+            self,
+            ArchiveFormat::Tar7z | ArchiveFormat::P7z | ArchiveFormat::Zip | ArchiveFormat::Rar
+        )
+    }
+
+    /// Whether this format can only contain one file.
+    #[inline]
+    pub const fn is_single_file(self) -> bool {
+        matches!(
+            self,
+            ArchiveFormat::Z
+                | ArchiveFormat::Gzip
+                | ArchiveFormat::Bzip2
+                | ArchiveFormat::Lz
+                | ArchiveFormat::Xz
+                | ArchiveFormat::Lzma
+                | ArchiveFormat::Zstd
+        )
+    }
+}
+
+impl Display for ArchiveFormat {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = XcompressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.strip_prefix('.').unwrap_or(s).to_ascii_lowercase();
+
+        let format = match name.as_str() {
+            "z" | "compress" => ArchiveFormat::Z,
+            "zip" => ArchiveFormat::Zip,
+            "gz" | "gzip" => ArchiveFormat::Gzip,
+            "bz2" | "bzip2" => ArchiveFormat::Bzip2,
+            "lz" | "lzip" => ArchiveFormat::Lz,
+            "xz" => ArchiveFormat::Xz,
+            "lzma" => ArchiveFormat::Lzma,
+            "7z" | "7zip" => ArchiveFormat::P7z,
+            "tar" => ArchiveFormat::Tar,
+            "tar.z" | "taz" => ArchiveFormat::TarZ,
+            "tar.gz" | "tgz" => ArchiveFormat::TarGzip,
+            "tar.bz2" | "tbz2" | "tbz" => ArchiveFormat::TarBzip2,
+            "tar.lz" => ArchiveFormat::TarLz,
+            "tar.xz" | "txz" => ArchiveFormat::TarXz,
+            "tar.lzma" | "tlz" => ArchiveFormat::TarLzma,
+            "tar.7z" | "t7z" => ArchiveFormat::Tar7z,
+            "tar.zst" | "tzst" => ArchiveFormat::TarZstd,
+            "rar" => ArchiveFormat::Rar,
+            "zst" | "zstd" => ArchiveFormat::Zstd,
+            _ => {
+                return Err(XcompressError::InvalidOption(format!(
+                    "Unknown archive format {s:?}. Accepted names: {}.",
+                    ArchiveFormat::NAMES
+                )))
+            },
+        };
+
+        Ok(format)
+    }
+}
+
+#[inline]
+fn is_tar_header(header: &[u8]) -> bool {
+    header.len() >= 262 && &header[257..262] == b"ustar"
+}
+
+fn read_fully<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut length = 0;
+
+    while length < buffer.len() {
+        match reader.read(&mut buffer[length..]) {
+            Ok(0) => break,
+            Ok(c) => length += c,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(length)
+}
+
+/// Decompress the beginning of a compressed stream to check whether it is a tar archive. Return `None` if it cannot be decided.
+fn peek_tar_in_compressed_stream(
+    input: Stdio,
+    format: ArchiveFormat,
+    executable_paths: &ExecutablePaths,
+) -> Option<bool> {
+    let mut command = match format {
+        // gzip can also decompress the files created by compress
+        ArchiveFormat::Z | ArchiveFormat::Gzip => {
+            command_args!(&executable_paths.gzip_path, "-d", "-c")
+        },
+        ArchiveFormat::Bzip2 => command_args!(&executable_paths.bzip2_path, "-d", "-c"),
+        ArchiveFormat::Lz => command_args!(&executable_paths.lzip_path, "-d", "-c"),
+        ArchiveFormat::Xz => command_args!(&executable_paths.xz_path, "-d", "-c"),
+        ArchiveFormat::Zstd => command_args!(&executable_paths.zstd_path, "-d", "-c"),
+        _ => return None,
+    };
+
+    command.stdin(input);
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::null());
+
+    let mut child = command.spawn().ok()?;
+
+    let mut buffer = [0u8; HEADER_SIZE];
+
+    let length = read_fully(child.stdout.as_mut().unwrap(), &mut buffer);
+
+    // the rest of the stream is not needed
+    let _ = child.kill();
+    let _ = child.wait();
+
+    let length = length.ok()?;
+
+    if length == 0 {
+        // the decompressor failed without any output, or the stream is empty
+        return None;
+    }
+
+    Some(is_tar_header(&buffer[..length]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_bytes() {
+        assert_eq!(
+            Some(ArchiveFormat::Gzip),
+            ArchiveFormat::get_archive_format_from_magic_bytes(&[0x1F, 0x8B, 0x08])
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Z),
+            ArchiveFormat::get_archive_format_from_magic_bytes(&[0x1F, 0x9D, 0x90])
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Bzip2),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"BZh91AY&SY")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Xz),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"\xFD7zXZ\x00\x00\x04")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Zstd),
+            ArchiveFormat::get_archive_format_from_magic_bytes(&[0x28, 0xB5, 0x2F, 0xFD, 0x04])
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Lz),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"LZIP\x01")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::P7z),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"7z\xBC\xAF\x27\x1C\x00\x04")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Rar),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"Rar!\x1A\x07\x00")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Rar),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"Rar!\x1A\x07\x01\x00")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Zip),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"PK\x03\x04\x14\x00")
+        );
+        assert_eq!(
+            Some(ArchiveFormat::Zip),
+            ArchiveFormat::get_archive_format_from_magic_bytes(b"PK\x05\x06\x00\x00")
+        );
+
+        let mut tar_header = vec![0; 512];
+
+        tar_header[257..263].copy_from_slice(b"ustar\0");
+
+        assert_eq!(
+            Some(ArchiveFormat::Tar),
+            ArchiveFormat::get_archive_format_from_magic_bytes(&tar_header)
+        );
+
+        // too short to have the ustar magic
+        assert_eq!(None, ArchiveFormat::get_archive_format_from_magic_bytes(&tar_header[..261]));
+        assert_eq!(None, ArchiveFormat::get_archive_format_from_magic_bytes(b""));
+        assert_eq!(None, ArchiveFormat::get_archive_format_from_magic_bytes(b"hello, world"));
+        assert_eq!(None, ArchiveFormat::get_archive_format_from_magic_bytes(b"Rar!\x1A\x07\x02"));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(ArchiveFormat::TarGzip, "tar.gz".parse().unwrap());
+        assert_eq!(ArchiveFormat::TarGzip, ".tgz".parse().unwrap());
+        assert_eq!(ArchiveFormat::TarZstd, "TAR.ZST".parse().unwrap());
+        assert_eq!(ArchiveFormat::TarBzip2, "tbz".parse().unwrap());
+        assert_eq!(ArchiveFormat::P7z, "7zip".parse().unwrap());
+        assert_eq!(ArchiveFormat::Z, "compress".parse().unwrap());
+        assert_eq!(ArchiveFormat::Zstd, ".zst".parse().unwrap());
+        assert_eq!(ArchiveFormat::Rar, "Rar".parse().unwrap());
+
+        assert!(matches!(
+            "tar.foo".parse::<ArchiveFormat>(),
+            Err(XcompressError::InvalidOption(_))
+        ));
+        assert!("".parse::<ArchiveFormat>().is_err());
+        assert!("..gz".parse::<ArchiveFormat>().is_err());
     }
 }
@@ -0,0 +1,1427 @@
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fs,
+    fs::File,
+    io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use execute::command_args;
+use path_absolutize::Absolutize;
+
+use crate::{
+    command::{
+        is_executable, is_stdio, run, run_accepting, run_pipeline, run_pipeline_to_writer,
+        supports_7z_memory_limit, type_password,
+    },
+    disk_space::{check_available_space, format_size, get_available_space, get_total_size},
+    error::IoResultExt,
+    priority::{check_nice, set_priority},
+    volume::{get_next_volume_path, VolumeWriter},
+    ArchiveFormat, ExecutablePaths, IoPriority, XcompressError,
+};
+
+/// The smallest size of a volume of a split archive.
+pub const MIN_SPLIT_SIZE: u64 = 65536;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum CompressionLevel {
+    #[default]
+    Default,
+    Best,
+    Fast,
+    /// A level from 0 (the fastest) to 9 (the smallest), which is mapped to the level of each program.
+    Level(u8),
+    /// A level of the program itself, which is passed to it directly.
+    Raw(u8),
+}
+
+/// The result of a successful archiving or extracting.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Report {
+    pub format:      ArchiveFormat,
+    /// The created archive file, the extracted file, or the directory which the files are extracted into.
+    pub output_path: PathBuf,
+    /// The things which did not go as usual but did not stop the work, like a skipped file. They are not printed by the library.
+    pub warnings:    Vec<String>,
+}
+
+/// Create archive files by the external programs.
+#[derive(Debug, Clone)]
+pub struct Archiver {
+    format:              ArchiveFormat,
+    level:               CompressionLevel,
+    password:            Option<String>,
+    split:               Option<u64>,
+    recovery_record:     Option<u8>,
+    dict_size:           Option<u64>,
+    long_window:         Option<u8>,
+    solid:               bool,
+    solid_block_size:    Option<u64>,
+    rsyncable:           bool,
+    allow_changed_files: bool,
+    fsync:               bool,
+    check_space:         bool,
+    memory_limit:        Option<u64>,
+    nice:                Option<i32>,
+    ionice:              Option<IoPriority>,
+    threads:             usize,
+    quiet:               bool,
+    executable_paths:    ExecutablePaths,
+}
+
+impl Archiver {
+    #[inline]
+    pub fn new(format: ArchiveFormat) -> Archiver {
+        Archiver {
+            format,
+            level: CompressionLevel::Default,
+            password: None,
+            split: None,
+            recovery_record: None,
+            dict_size: None,
+            long_window: None,
+            solid: false,
+            solid_block_size: None,
+            rsyncable: false,
+            allow_changed_files: false,
+            fsync: false,
+            check_space: true,
+            memory_limit: None,
+            nice: None,
+            ionice: None,
+            threads: num_cpus::get(),
+            quiet: false,
+            executable_paths: ExecutablePaths::default(),
+        }
+    }
+
+    #[inline]
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        self.level = level;
+
+        self
+    }
+
+    /// Encrypt the archive. Only 7Z, ZIP and RAR are supported. An empty password means no encryption.
+    #[inline]
+    pub fn password<S: Into<String>>(mut self, password: S) -> Self {
+        self.password = Some(password.into());
+
+        self
+    }
+
+    /// Split the archive into volumes of this size in bytes. 7Z, ZIP and RAR archives are split by their programs. The other formats are written as a stream into numbered volumes, like `foo.tar.zst.001`, `foo.tar.zst.002`, and so on.
+    #[inline]
+    pub fn split(mut self, size: u64) -> Self {
+        self.split = Some(size);
+
+        self
+    }
+
+    /// Add a recovery record in percent. Only RAR is supported.
+    #[inline]
+    pub fn recovery_record(mut self, percent: u8) -> Self {
+        self.recovery_record = Some(percent);
+
+        self
+    }
+
+    /// The dictionary size in bytes. Only 7Z, XZ, LZMA and RAR are supported.
+    #[inline]
+    pub fn dict_size(mut self, size: u64) -> Self {
+        self.dict_size = Some(size);
+
+        self
+    }
+
+    /// The base 2 logarithm of the window size of the long distance matching, from 10 to 31. Only ZSTD is supported.
+    #[inline]
+    pub fn long_window(mut self, window_log: u8) -> Self {
+        self.long_window = Some(window_log);
+
+        self
+    }
+
+    /// Compress the files as a continuous stream. Only 7Z and RAR are supported.
+    #[inline]
+    pub fn solid(mut self, solid: bool) -> Self {
+        self.solid = solid;
+
+        self
+    }
+
+    /// The size in bytes of each solid block, which implies `solid`. Only 7Z is supported.
+    #[inline]
+    pub fn solid_block_size(mut self, size: u64) -> Self {
+        self.solid_block_size = Some(size);
+
+        self
+    }
+
+    /// Make the compressed data friendly to rsync. Only GZIP and ZSTD are supported.
+    #[inline]
+    pub fn rsyncable(mut self, rsyncable: bool) -> Self {
+        self.rsyncable = rsyncable;
+
+        self
+    }
+
+    /// Treat the files which are changed while tar is reading them as a warning instead of an error. Only the formats archived by tar are affected.
+    #[inline]
+    pub fn allow_changed_files(mut self, allow_changed_files: bool) -> Self {
+        self.allow_changed_files = allow_changed_files;
+
+        self
+    }
+
+    /// Flush the archive files to the disk before renaming them to their final names.
+    #[inline]
+    pub fn fsync(mut self, fsync: bool) -> Self {
+        self.fsync = fsync;
+
+        self
+    }
+
+    /// Check whether the filesystem has enough free space for the input files before archiving. It is an error for TAR, which does not compress the files, and a warning for the other formats. The default is `true`.
+    #[inline]
+    pub fn check_space(mut self, check_space: bool) -> Self {
+        self.check_space = check_space;
+
+        self
+    }
+
+    /// Limit the memory usage of the programs in bytes. Only xz, lzma and 7z are affected, and 7z needs to be 7-Zip 22.00 or later. xz reduces the compression settings to fit in the limit.
+    #[inline]
+    pub fn memory_limit(mut self, size: u64) -> Self {
+        self.memory_limit = Some(size);
+
+        self
+    }
+
+    /// The niceness of the programs, from -20 (the highest priority) to 19 (the lowest priority).
+    #[inline]
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+
+        self
+    }
+
+    /// The I/O scheduling class and priority of the programs.
+    #[inline]
+    pub fn ionice(mut self, ionice: IoPriority) -> Self {
+        self.ionice = Some(ionice);
+
+        self
+    }
+
+    /// The number of threads which the programs can use. `0` means the number of CPUs, which is the default.
+    #[inline]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = if threads == 0 { num_cpus::get() } else { threads };
+
+        self
+    }
+
+    /// Make the programs output less messages.
+    #[inline]
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+
+        self
+    }
+
+    #[inline]
+    pub fn executable_paths(mut self, executable_paths: ExecutablePaths) -> Self {
+        self.executable_paths = executable_paths;
+
+        self
+    }
+}
+
+impl Archiver {
+    /// Archive the input files and directories into the output file. An existing output file is replaced only after the archive is created successfully.
+    ///
+    /// The input path can be `-` to compress stdin, for the single-file formats, or for the compressed TAR formats if stdin is a tar stream. The output path can be `-` to write the archive to stdout, for TAR and the formats which can be compressed as a stream.
+    pub fn create<I: AsRef<Path>, O: AsRef<Path>>(
+        &self,
+        input_paths: &[I],
+        output_path: O,
+    ) -> Result<Report, XcompressError> {
+        let format = self.format;
+
+        if input_paths.is_empty() {
+            return Err(XcompressError::InvalidOption(String::from("No input files.")));
+        }
+
+        check_nice(self.nice)?;
+
+        let input_paths = input_paths
+            .iter()
+            .map(|input_path| {
+                let input_path = input_path.as_ref();
+
+                if is_stdio(input_path) {
+                    Ok(input_path.to_path_buf())
+                } else {
+                    input_path.canonicalize().with_path(input_path)
+                }
+            })
+            .collect::<Result<Vec<PathBuf>, XcompressError>>()?;
+
+        if input_paths.iter().any(|input_path| is_stdio(input_path)) {
+            if input_paths.len() > 1 {
+                return Err(XcompressError::InvalidOption(String::from(
+                    "Stdin cannot be archived with other files.",
+                )));
+            }
+
+            if !is_streamable(format) || format == ArchiveFormat::Tar {
+                return Err(XcompressError::UnsupportedOption {
+                    option: "stdin",
+                    format,
+                    supported: "Z, GZIP, BZIP2, LZ, XZ, LZMA, ZSTD and their TAR formats",
+                });
+            }
+        }
+
+        if self.password.is_some() && !format.supports_password() {
+            return Err(XcompressError::UnsupportedOption {
+                option: "password",
+                format,
+                supported: "7Z, ZIP and RAR",
+            });
+        }
+
+        if let Some(split) = self.split {
+            if split < MIN_SPLIT_SIZE {
+                return Err(XcompressError::InvalidOption(String::from(
+                    "The split size is too small.",
+                )));
+            }
+        }
+
+        if self.recovery_record.is_some() && format != ArchiveFormat::Rar {
+            return Err(XcompressError::UnsupportedOption {
+                option: "recovery-record",
+                format,
+                supported: "RAR",
+            });
+        }
+
+        if self.dict_size.is_some()
+            && !matches!(
+                format,
+                ArchiveFormat::P7z
+                    | ArchiveFormat::Tar7z
+                    | ArchiveFormat::Xz
+                    | ArchiveFormat::TarXz
+                    | ArchiveFormat::Lzma
+                    | ArchiveFormat::TarLzma
+                    | ArchiveFormat::Rar
+            )
+        {
+            return Err(XcompressError::UnsupportedOption {
+                option: "dict-size",
+                format,
+                supported: "7Z, XZ, LZMA and RAR",
+            });
+        }
+
+        if self.long_window.is_some()
+            && !matches!(format, ArchiveFormat::Zstd | ArchiveFormat::TarZstd)
+        {
+            return Err(XcompressError::UnsupportedOption {
+                option: "long-window",
+                format,
+                supported: "ZSTD",
+            });
+        }
+
+        if let Some(long_window) = self.long_window {
+            if !(10..=31).contains(&long_window) {
+                return Err(XcompressError::InvalidOption(String::from(
+                    "The long window should be from 10 to 31.",
+                )));
+            }
+        }
+
+        if self.solid && !matches!(format, ArchiveFormat::P7z | ArchiveFormat::Rar) {
+            return Err(XcompressError::UnsupportedOption {
+                option: "solid",
+                format,
+                supported: "7Z and RAR",
+            });
+        }
+
+        if self.solid_block_size.is_some() && format != ArchiveFormat::P7z {
+            return Err(XcompressError::UnsupportedOption {
+                option: "solid-block-size",
+                format,
+                supported: "7Z",
+            });
+        }
+
+        if self.rsyncable
+            && !matches!(
+                format,
+                ArchiveFormat::Gzip
+                    | ArchiveFormat::TarGzip
+                    | ArchiveFormat::Zstd
+                    | ArchiveFormat::TarZstd
+            )
+        {
+            return Err(XcompressError::UnsupportedOption {
+                option: "rsyncable",
+                format,
+                supported: "GZIP and ZSTD",
+            });
+        }
+
+        match self.level {
+            CompressionLevel::Level(level) if level > 9 => {
+                return Err(XcompressError::InvalidOption(String::from(
+                    "The compression level should be from 0 to 9.",
+                )));
+            },
+            CompressionLevel::Raw(level) => check_raw_level(format, level)?,
+            _ => (),
+        }
+
+        if format.is_single_file() && (input_paths.len() > 1 || input_paths[0].is_dir()) {
+            return Err(XcompressError::InvalidOption(format!(
+                "Obviously, you should use .{} for filename extension to support multiple files.",
+                format.to_tar_format().unwrap()
+            )));
+        }
+
+        let output_path = output_path.as_ref();
+
+        if is_stdio(output_path) {
+            if self.split.is_some() {
+                return Err(XcompressError::InvalidOption(String::from(
+                    "The archive written to stdout cannot be split.",
+                )));
+            }
+
+            if !is_streamable(format) {
+                return Err(XcompressError::UnsupportedOption {
+                    option: "stdout",
+                    format,
+                    supported: "TAR, Z, GZIP, BZIP2, LZ, XZ, LZMA, ZSTD and their TAR formats",
+                });
+            }
+
+            // there is no file to be replaced
+            self.create_archive(&input_paths, output_path)?;
+
+            return Ok(Report {
+                format,
+                output_path: output_path.to_path_buf(),
+                warnings: Vec::new(),
+            });
+        }
+
+        let output_path = match output_path.canonicalize() {
+            Ok(output_path) => {
+                if output_path.is_dir() {
+                    return Err(XcompressError::InvalidPath {
+                        path:   output_path,
+                        reason: "is a directory",
+                    });
+                }
+
+                output_path
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                output_path.absolutize().with_path(output_path)?.into_owned()
+            },
+            Err(error) => return Err(error).with_path(output_path),
+        };
+
+        let mut warnings = Vec::new();
+
+        // the size of stdin is unknown
+        if self.check_space && !is_stdio(input_paths[0].as_path()) {
+            self.check_available_space(&input_paths, output_path.as_path(), &mut warnings)?;
+        }
+
+        // the archive is created beside the output file with a temporary name, and renamed after the programs succeed, so that an existing archive is not destroyed by a failed run
+        let output_dir = output_path.parent().unwrap();
+        let temporary_prefix = get_temporary_prefix();
+
+        let mut temporary_file_name = OsString::from(temporary_prefix.as_str());
+        temporary_file_name.push(output_path.file_name().unwrap());
+
+        let temporary_path = output_dir.join(temporary_file_name);
+
+        let result = self.create_archive(&input_paths, temporary_path.as_path()).and_then(|_| {
+            let file_paths =
+                rename_temporary_files(output_dir, temporary_prefix.as_str(), self.fsync)?;
+
+            // the volumes of an earlier split archive with the same name would be read as a part of this one
+            if self.split.is_some() {
+                delete_stale_volumes(&file_paths);
+            }
+
+            Ok(())
+        });
+
+        if result.is_err() {
+            delete_temporary_files(output_dir, temporary_prefix.as_str());
+        }
+
+        result.map(|_| Report {
+            format,
+            output_path,
+            warnings,
+        })
+    }
+
+    fn create_archive(
+        &self,
+        input_paths: &[PathBuf],
+        output_path: &Path,
+    ) -> Result<(), XcompressError> {
+        let executable_paths = &self.executable_paths;
+        let password = self.password.as_deref().unwrap_or("");
+
+        match self.format {
+            ArchiveFormat::TarZ
+            | ArchiveFormat::TarGzip
+            | ArchiveFormat::TarBzip2
+            | ArchiveFormat::TarLz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarZstd => {
+                let compressor_format = self.format.to_compressor_format().unwrap();
+
+                let mut command2 = self.create_compress_command(compressor_format, OsStr::new("-"));
+
+                if is_stdio(input_paths[0].as_path()) {
+                    // stdin is already a tar stream
+                    self.write_archive(&mut [&mut command2], output_path, &[get_accepted_codes(
+                        compressor_format,
+                    )])?;
+
+                    return Ok(());
+                }
+
+                let mut command1 = command_args!(&executable_paths.tar_path, "-c", "-f", "-");
+
+                add_tar_input_paths(&mut command1, input_paths);
+
+                self.write_archive(&mut [&mut command1, &mut command2], output_path, &[
+                    self.get_tar_accepted_codes(),
+                    get_accepted_codes(compressor_format),
+                ])?;
+            },
+            ArchiveFormat::Tar7z if !password.is_empty() => {
+                // stdin of 7z is the pseudo terminal for typing the password, so the TAR stream is written to a temporary file instead of being piped
+                let mut temporary_dir_name = output_path.file_name().unwrap().to_os_string();
+                temporary_dir_name.push(".tmp");
+
+                let temporary_dir = output_path.with_file_name(temporary_dir_name);
+
+                fs::create_dir(temporary_dir.as_path()).with_path(temporary_dir.as_path())?;
+
+                let tar_file_name = trim_temporary_prefix(
+                    output_path.file_stem().unwrap().to_str().unwrap_or("archive.tar"),
+                );
+
+                let tar_path = temporary_dir.join(tar_file_name);
+
+                let result = (|| {
+                    let mut command1 = command_args!(&executable_paths.tar_path, "-c", "-f");
+
+                    command1.arg(tar_path.as_path());
+
+                    add_tar_input_paths(&mut command1, input_paths);
+
+                    let mut command2 = command_args!(
+                        &executable_paths.p7z_path,
+                        "a",
+                        "-t7z",
+                        "-aoa",
+                        format!("-mmt{}", self.threads),
+                    );
+
+                    self.add_7z_options(&mut command2, password)?;
+
+                    command2.arg(output_path);
+                    command2.arg(tar_path.as_path());
+
+                    if self.quiet {
+                        command2.stdout(Stdio::null());
+                    }
+
+                    self.set_priority(&mut [&mut command1, &mut command2]);
+
+                    run_accepting(&mut command1, self.quiet, self.get_tar_accepted_codes())?;
+                    run(&mut command2, self.quiet)
+                })();
+
+                let _ = fs::remove_dir_all(temporary_dir.as_path());
+
+                result?;
+            },
+            ArchiveFormat::Tar7z => {
+                let mut command1 = command_args!(&executable_paths.tar_path, "-c", "-f", "-");
+
+                add_tar_input_paths(&mut command1, input_paths);
+
+                let mut command2 = command_args!(
+                    &executable_paths.p7z_path,
+                    "a",
+                    "-t7z",
+                    "-aoa",
+                    format!("-mmt{}", self.threads),
+                    "-si",
+                );
+
+                self.add_7z_options(&mut command2, password)?;
+
+                command2.arg(output_path);
+
+                if self.quiet {
+                    command2.stdout(Stdio::null());
+                }
+
+                self.set_priority(&mut [&mut command1, &mut command2]);
+
+                run_pipeline(&mut [&mut command1, &mut command2], self.quiet, &[
+                    self.get_tar_accepted_codes()
+                ])?;
+            },
+            ArchiveFormat::Tar => {
+                let mut command = command_args!(&executable_paths.tar_path, "-c");
+
+                if !self.quiet {
+                    command.arg("-v");
+                }
+
+                if self.split.is_some() {
+                    command.args(["-f", "-"]);
+
+                    add_tar_input_paths(&mut command, input_paths);
+
+                    self.write_archive(&mut [&mut command], output_path, &[
+                        self.get_tar_accepted_codes()
+                    ])?;
+
+                    return Ok(());
+                }
+
+                command.arg("-f");
+                command.arg(output_path);
+
+                add_tar_input_paths(&mut command, input_paths);
+
+                self.set_priority(&mut [&mut command]);
+
+                run_accepting(&mut command, self.quiet, self.get_tar_accepted_codes())?;
+            },
+            ArchiveFormat::Z
+            | ArchiveFormat::Gzip
+            | ArchiveFormat::Bzip2
+            | ArchiveFormat::Lz
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Lzma
+            | ArchiveFormat::Zstd => {
+                let mut command =
+                    self.create_compress_command(self.format, input_paths[0].as_os_str());
+
+                self.write_archive(&mut [&mut command], output_path, &[get_accepted_codes(
+                    self.format,
+                )])?;
+            },
+            ArchiveFormat::P7z => {
+                let mut command = command_args!(
+                    &executable_paths.p7z_path,
+                    "a",
+                    "-t7z",
+                    "-aoa",
+                    format!("-mmt{}", self.threads)
+                );
+
+                self.add_7z_options(&mut command, password)?;
+
+                command.arg(output_path);
+                command.args(input_paths);
+
+                if self.quiet {
+                    command.stdout(Stdio::null());
+                }
+
+                self.set_priority(&mut [&mut command]);
+
+                run(&mut command, self.quiet)?;
+            },
+            ArchiveFormat::Zip => {
+                // 7z cannot create split ZIP files which can be read by other programs, so `zip -s` is used afterwards
+                let output_tmp_path = if self.split.is_some() {
+                    let new_filename =
+                        format!("{}.tmp.zip", output_path.file_stem().unwrap().to_string_lossy());
+
+                    let output_tmp_path = output_path.parent().unwrap().join(new_filename);
+
+                    if let Ok(metadata) = output_tmp_path.metadata() {
+                        if metadata.is_dir() {
+                            return Err(XcompressError::InvalidPath {
+                                path:   output_tmp_path,
+                                reason: "is a directory",
+                            });
+                        } else {
+                            fs::remove_file(output_tmp_path.as_path())
+                                .with_path(output_tmp_path.as_path())?;
+                        }
+                    }
+
+                    Cow::from(output_tmp_path)
+                } else {
+                    Cow::from(output_path)
+                };
+
+                let mut command = command_args!(
+                    &executable_paths.p7z_path,
+                    "a",
+                    "-tzip",
+                    "-aoa",
+                    format!("-mmt{}", self.threads)
+                );
+
+                match self.level {
+                    CompressionLevel::Best => {
+                        command.arg("-mx");
+                    },
+                    CompressionLevel::Fast => {
+                        command.arg("-mx=0");
+                    },
+                    CompressionLevel::Level(level) | CompressionLevel::Raw(level) => {
+                        command.arg(format!("-mx={level}"));
+                    },
+                    CompressionLevel::Default => (),
+                }
+
+                if !password.is_empty() {
+                    add_password_option(&mut command, "-p", password);
+                }
+
+                command.arg(output_tmp_path.as_ref());
+                command.args(input_paths);
+
+                if self.quiet {
+                    command.stdout(Stdio::null());
+                }
+
+                self.set_priority(&mut [&mut command]);
+
+                run(&mut command, self.quiet)?;
+
+                if let Some(split) = self.split {
+                    let mut command = command_args!(
+                        &executable_paths.zip_path,
+                        "-s",
+                        format!("{}k", get_size_in_kib(split))
+                    );
+
+                    match self.level {
+                        CompressionLevel::Best => {
+                            command.arg("-9");
+                        },
+                        CompressionLevel::Fast => {
+                            command.arg("-0");
+                        },
+                        CompressionLevel::Level(level) | CompressionLevel::Raw(level) => {
+                            command.arg(format!("-{level}"));
+                        },
+                        CompressionLevel::Default => (),
+                    }
+
+                    // the entries are copied without being decrypted, so the password is not needed
+                    if self.quiet {
+                        command.arg("-q");
+                    }
+
+                    command.arg(output_tmp_path.as_ref());
+                    command.arg("--out");
+                    command.arg(output_path);
+
+                    self.set_priority(&mut [&mut command]);
+
+                    let result = run(&mut command, self.quiet);
+
+                    try_delete_file(output_tmp_path.as_ref());
+
+                    result?;
+                }
+            },
+            ArchiveFormat::Rar => {
+                let mut command = command_args!(
+                    &executable_paths.rar_path,
+                    "a",
+                    "-ep1",
+                    format!("-mt{}", self.threads)
+                );
+
+                match self.level {
+                    CompressionLevel::Best => {
+                        command.args(["-ma5", "-m5", "-s"]);
+                    },
+                    CompressionLevel::Fast => {
+                        command.arg("-m0");
+                    },
+                    CompressionLevel::Level(level) => {
+                        command.arg(format!("-m{}", get_rar_level(level)));
+                    },
+                    CompressionLevel::Raw(level) => {
+                        command.arg(format!("-m{level}"));
+                    },
+                    CompressionLevel::Default => (),
+                }
+
+                if let Some(dict_size) = self.dict_size {
+                    command.arg(format!("-md{}k", get_size_in_kib(dict_size)));
+                }
+
+                if self.solid {
+                    command.arg("-s");
+                }
+
+                if !password.is_empty() {
+                    add_password_option(&mut command, "-hp", password);
+                }
+
+                if self.quiet {
+                    command.arg("-idq");
+                }
+
+                if let Some(split) = self.split {
+                    command.arg(format!("-v{}k", get_size_in_kib(split)));
+                }
+
+                if let Some(rr) = self.recovery_record {
+                    command.arg(format!("-rr{rr}"));
+                }
+
+                command.arg(output_path);
+                command.args(input_paths);
+
+                self.set_priority(&mut [&mut command]);
+
+                run(&mut command, self.quiet)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Create a command which compresses a file (or stdin if the input path is `-`) to stdout. The multi-threaded programs are preferred.
+    fn create_compress_command(&self, format: ArchiveFormat, input_path: &OsStr) -> Command {
+        let executable_paths = &self.executable_paths;
+        let threads = self.threads.to_string();
+        let threads = threads.as_str();
+        let multi_threaded = self.threads > 1;
+
+        let mut command = match format {
+            ArchiveFormat::Z => {
+                // compress has no compression levels and no quiet mode
+                return command_args!(&executable_paths.compress_path, "-c", input_path);
+            },
+            ArchiveFormat::Gzip => {
+                let mut command = if (multi_threaded || self.is_zopfli_level())
+                    && is_executable(&executable_paths.pigz_path, "-V")
+                {
+                    let mut command =
+                        command_args!(&executable_paths.pigz_path, "-c", "-p", threads);
+
+                    match self.level {
+                        // zopfli
+                        CompressionLevel::Best => {
+                            command.arg("-11");
+                        },
+                        CompressionLevel::Fast => {
+                            command.arg("-1");
+                        },
+                        CompressionLevel::Level(9) => {
+                            command.arg("-11");
+                        },
+                        CompressionLevel::Level(level) => {
+                            command.arg(format!("-{}", level.max(1)));
+                        },
+                        CompressionLevel::Raw(level) => {
+                            command.arg(format!("-{level}"));
+                        },
+                        CompressionLevel::Default => (),
+                    }
+
+                    command
+                } else {
+                    let mut command = command_args!(&executable_paths.gzip_path, "-c");
+
+                    add_level_option(&mut command, self.level, 1);
+
+                    command
+                };
+
+                if self.rsyncable {
+                    command.arg("--rsyncable");
+                }
+
+                command
+            },
+            ArchiveFormat::Bzip2 => {
+                let mut command = if multi_threaded
+                    && is_executable(&executable_paths.lbzip2_path, "-V")
+                {
+                    command_args!(&executable_paths.lbzip2_path, "-z", "-c", "-n", threads)
+                } else if multi_threaded && is_executable(&executable_paths.pbzip2_path, "-V") {
+                    command_args!(&executable_paths.pbzip2_path, "-z", "-c", format!("-p{threads}"))
+                } else {
+                    command_args!(&executable_paths.bzip2_path, "-z", "-c")
+                };
+
+                add_level_option(&mut command, self.level, 1);
+
+                command
+            },
+            ArchiveFormat::Lz => {
+                let mut command =
+                    if multi_threaded && is_executable(&executable_paths.plzip_path, "-V") {
+                        command_args!(&executable_paths.plzip_path, "-F", "-c", "-n", threads)
+                    } else {
+                        command_args!(&executable_paths.lzip_path, "-F", "-c")
+                    };
+
+                add_level_option(&mut command, self.level, 0);
+
+                command
+            },
+            ArchiveFormat::Xz | ArchiveFormat::Lzma => {
+                // pxz does not support the memory usage limit
+                let mut command = if multi_threaded
+                    && self.memory_limit.is_none()
+                    && is_executable(&executable_paths.pxz_path, "-V")
+                {
+                    let mut command =
+                        command_args!(&executable_paths.pxz_path, "-z", "-c", "-T", threads);
+
+                    if format == ArchiveFormat::Lzma {
+                        command.args(["-F", "lzma"]);
+                    }
+
+                    command
+                } else if format == ArchiveFormat::Lzma {
+                    command_args!(&executable_paths.lzma_path, "-z", "-c")
+                } else {
+                    command_args!(&executable_paths.xz_path, "-z", "-c", "-T", threads)
+                };
+
+                match self.level {
+                    CompressionLevel::Best => {
+                        command.args(["-9", "-e"]);
+                    },
+                    CompressionLevel::Fast => {
+                        command.arg("-0");
+                    },
+                    CompressionLevel::Level(9) => {
+                        command.args(["-9", "-e"]);
+                    },
+                    CompressionLevel::Level(level) | CompressionLevel::Raw(level) => {
+                        command.arg(format!("-{level}"));
+                    },
+                    CompressionLevel::Default => (),
+                }
+
+                if let Some(memory_limit) = self.memory_limit {
+                    command.arg(format!("--memlimit-compress={memory_limit}"));
+                }
+
+                if let Some(dict_size) = self.dict_size {
+                    // a filter option replaces the preset, so the preset is given in it again
+                    let preset = match self.level {
+                        CompressionLevel::Best | CompressionLevel::Level(9) => String::from("9e"),
+                        CompressionLevel::Fast => String::from("0"),
+                        CompressionLevel::Level(level) | CompressionLevel::Raw(level) => {
+                            level.to_string()
+                        },
+                        CompressionLevel::Default => String::from("6"),
+                    };
+
+                    let filter = if format == ArchiveFormat::Lzma { "lzma1" } else { "lzma2" };
+
+                    command.arg(format!("--{filter}=preset={preset},dict={dict_size}"));
+                }
+
+                command
+            },
+            ArchiveFormat::Zstd => {
+                // pzstd supports neither the long distance matching nor the rsyncable mode
+                let mut command = if multi_threaded
+                    && self.long_window.is_none()
+                    && !self.rsyncable
+                    && is_executable(&executable_paths.pzstd_path, "-V")
+                {
+                    command_args!(&executable_paths.pzstd_path, "-c", "-p", threads)
+                } else {
+                    command_args!(&executable_paths.zstd_path, "-c", format!("-T{threads}"))
+                };
+
+                match self.level {
+                    CompressionLevel::Best => {
+                        command.args(["--ultra", "-22"]);
+                    },
+                    CompressionLevel::Fast => {
+                        command.arg("-1");
+                    },
+                    CompressionLevel::Level(level) => {
+                        add_zstd_level_option(&mut command, get_zstd_level(level));
+                    },
+                    CompressionLevel::Raw(level) => {
+                        add_zstd_level_option(&mut command, level);
+                    },
+                    CompressionLevel::Default => (),
+                }
+
+                if let Some(long_window) = self.long_window {
+                    command.arg(format!("--long={long_window}"));
+                }
+
+                if self.rsyncable {
+                    command.arg("--rsyncable");
+                }
+
+                command
+            },
+            _ => unreachable!(),
+        };
+
+        if self.quiet {
+            command.arg("-q");
+        }
+
+        command.arg(input_path);
+
+        command
+    }
+
+    fn check_available_space(
+        &self,
+        input_paths: &[PathBuf],
+        output_path: &Path,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), XcompressError> {
+        // the compressed size is unknown, so the size of the input files is the estimate
+        let required = get_total_size(input_paths)?;
+
+        if self.format == ArchiveFormat::Tar {
+            return check_available_space(output_path, required);
+        }
+
+        if let Ok(available) = get_available_space(output_path) {
+            if required > available {
+                warnings.push(format!(
+                    "Warning: {:?} may not have enough free space. The input files are {}, but \
+                     only {} is available.",
+                    output_path,
+                    format_size(required),
+                    format_size(available)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the commands which write the archive to the stdout of the last one, and save it to the output file, to stdout, or into volumes if it is split.
+    fn write_archive(
+        &self,
+        commands: &mut [&mut Command],
+        output_path: &Path,
+        accepted_codes: &[&[i32]],
+    ) -> Result<(), XcompressError> {
+        self.set_priority(commands);
+
+        if let Some(split) = self.split {
+            let mut writer = VolumeWriter::create(output_path, split)?;
+
+            return run_pipeline_to_writer(
+                commands,
+                self.quiet,
+                accepted_codes,
+                &mut writer,
+                &mut |_| Ok(()),
+            );
+        }
+
+        commands.last_mut().unwrap().stdout(create_output(output_path)?);
+
+        run_pipeline(commands, self.quiet, accepted_codes).map(|_| ())
+    }
+
+    #[inline]
+    fn set_priority(&self, commands: &mut [&mut Command]) {
+        for command in commands {
+            set_priority(command, self.nice, self.ionice);
+        }
+    }
+
+    /// pigz can compress with zopfli, which gzip cannot.
+    #[inline]
+    fn is_zopfli_level(&self) -> bool {
+        matches!(
+            self.level,
+            CompressionLevel::Best | CompressionLevel::Level(9) | CompressionLevel::Raw(11)
+        )
+    }
+
+    /// tar exits with 1 if some files are changed while being read.
+    #[inline]
+    fn get_tar_accepted_codes(&self) -> &'static [i32] {
+        if self.allow_changed_files {
+            &[1]
+        } else {
+            &[]
+        }
+    }
+
+    fn add_7z_options(&self, command: &mut Command, password: &str) -> Result<(), XcompressError> {
+        match self.level {
+            CompressionLevel::Best => {
+                command.args(["-m0=lzma2", "-mx", "-ms=on"]);
+            },
+            CompressionLevel::Fast => {
+                command.arg("-m0=copy");
+            },
+            CompressionLevel::Level(level) | CompressionLevel::Raw(level) => {
+                command.arg(format!("-mx={level}"));
+            },
+            CompressionLevel::Default => (),
+        }
+
+        if let Some(dict_size) = self.dict_size {
+            command.arg(format!("-md={dict_size}b"));
+        }
+
+        if let Some(memory_limit) = self.memory_limit {
+            if !supports_7z_memory_limit(&self.executable_paths.p7z_path) {
+                return Err(XcompressError::UnsupportedOption {
+                    option:    "memory-limit",
+                    format:    self.format,
+                    supported: "XZ, LZMA, and 7Z with 7-Zip 22.00 or later",
+                });
+            }
+
+            command.arg(format!("-mmemuse={memory_limit}b"));
+        }
+
+        if let Some(solid_block_size) = self.solid_block_size {
+            command.arg(format!("-ms={solid_block_size}b"));
+        } else if self.solid {
+            command.arg("-ms=on");
+        }
+
+        if !password.is_empty() {
+            command.arg("-mhe=on");
+
+            add_password_option(command, "-p", password);
+        }
+
+        if let Some(split) = self.split {
+            command.arg(format!("-v{}k", get_size_in_kib(split)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Put the password in the command with the option, like `-p`. The password is typed through a pseudo terminal if possible.
+#[inline]
+fn add_password_option(command: &mut Command, option: &str, password: &str) {
+    if type_password(command, password) {
+        command.arg(option);
+    } else {
+        command.arg(format!("{option}{password}"));
+    }
+}
+
+/// TAR and the formats which are compressed by a single stream can be read from stdin or written to stdout.
+#[inline]
+pub(crate) fn is_streamable(format: ArchiveFormat) -> bool {
+    format == ArchiveFormat::Tar
+        || format.is_single_file()
+        || matches!(format.to_compressor_format(), Some(compressor_format) if compressor_format.is_single_file())
+}
+
+/// Create the output file, or use stdout if the path is `-`.
+#[inline]
+fn create_output(output_path: &Path) -> Result<Stdio, XcompressError> {
+    if is_stdio(output_path) {
+        Ok(Stdio::inherit())
+    } else {
+        Ok(File::create(output_path).with_path(output_path)?.into())
+    }
+}
+
+#[inline]
+fn add_tar_input_paths(command: &mut Command, input_paths: &[PathBuf]) {
+    for input_path in input_paths {
+        command.arg("-C");
+        command.arg(input_path.parent().unwrap());
+        command.arg(input_path.file_name().unwrap());
+    }
+}
+
+/// For the programs whose levels are from `min_level` to 9, like gzip and bzip2.
+#[inline]
+fn add_level_option(command: &mut Command, level: CompressionLevel, min_level: u8) {
+    match level {
+        CompressionLevel::Best => {
+            command.arg("-9");
+        },
+        CompressionLevel::Fast => {
+            command.arg("-1");
+        },
+        CompressionLevel::Level(level) => {
+            command.arg(format!("-{}", level.max(min_level)));
+        },
+        CompressionLevel::Raw(level) => {
+            command.arg(format!("-{level}"));
+        },
+        CompressionLevel::Default => (),
+    }
+}
+
+/// The levels above 19 need `--ultra`.
+#[inline]
+fn add_zstd_level_option(command: &mut Command, level: u8) {
+    if level > 19 {
+        command.arg("--ultra");
+    }
+
+    command.arg(format!("-{level}"));
+}
+
+/// Map a level from 0 to 9 to a level of zstd from 1 to 22. The default level of zstd, 3, is 2.
+#[inline]
+fn get_zstd_level(level: u8) -> u8 {
+    const ZSTD_LEVELS: [u8; 10] = [1, 2, 3, 5, 7, 9, 12, 15, 19, 22];
+
+    ZSTD_LEVELS[level.min(9) as usize]
+}
+
+/// Map a level from 0 to 9 to a method of rar from 0 (store) to 5. The default method of rar, 3, is 5.
+#[inline]
+fn get_rar_level(level: u8) -> u8 {
+    (level.min(9) * 5 + 4) / 9
+}
+
+/// Check whether a level is accepted by the programs of the format, for `CompressionLevel::Raw`. The formats without compression levels ignore it.
+fn check_raw_level(format: ArchiveFormat, level: u8) -> Result<(), XcompressError> {
+    let (valid, levels) = match format {
+        ArchiveFormat::Gzip | ArchiveFormat::TarGzip => {
+            (matches!(level, 1..=9 | 11), "from 1 to 9, or 11 (pigz only)")
+        },
+        ArchiveFormat::Bzip2 | ArchiveFormat::TarBzip2 => ((1..=9).contains(&level), "from 1 to 9"),
+        ArchiveFormat::Lz
+        | ArchiveFormat::TarLz
+        | ArchiveFormat::Xz
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::Lzma
+        | ArchiveFormat::TarLzma
+        | ArchiveFormat::P7z
+        | ArchiveFormat::Tar7z
+        | ArchiveFormat::Zip => (level <= 9, "from 0 to 9"),
+        ArchiveFormat::Zstd | ArchiveFormat::TarZstd => ((1..=22).contains(&level), "from 1 to 22"),
+        ArchiveFormat::Rar => (level <= 5, "from 0 to 5"),
+        ArchiveFormat::Z | ArchiveFormat::TarZ | ArchiveFormat::Tar => return Ok(()),
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(XcompressError::InvalidOption(format!(
+            "The raw compression level of {format} should be {levels}."
+        )))
+    }
+}
+
+/// compress exits with 2 if the output is larger than the input, which is not an error.
+#[inline]
+fn get_accepted_codes(format: ArchiveFormat) -> &'static [i32] {
+    match format {
+        ArchiveFormat::Z => &[2],
+        _ => &[],
+    }
+}
+
+#[inline]
+fn get_size_in_kib(size: u64) -> u64 {
+    (size as f64 / 1024.0).round() as u64
+}
+
+/// The prefix of the names of the temporary files of an archive, which is unique in this process.
+pub(crate) fn get_temporary_prefix() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    format!(".xcompress-{}-{}.", process::id(), COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Remove the temporary prefix from a file name, if it has one.
+fn trim_temporary_prefix(file_name: &str) -> &str {
+    file_name
+        .strip_prefix(".xcompress-")
+        .and_then(|s| s.split_once('.'))
+        .filter(|(id, _)| {
+            id.split('-').all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(|(_, file_name)| file_name)
+        .unwrap_or(file_name)
+}
+
+/// Find the files created by the programs with the temporary prefix, including the volumes of a split archive.
+fn get_temporary_files(dir: &Path, prefix: &str) -> Result<Vec<PathBuf>, XcompressError> {
+    let mut file_paths = Vec::new();
+
+    for entry in fs::read_dir(dir).with_path(dir)? {
+        let entry = entry.with_path(dir)?;
+
+        if entry.file_name().as_bytes().starts_with(prefix.as_bytes()) {
+            file_paths.push(entry.path());
+        }
+    }
+
+    Ok(file_paths)
+}
+
+/// Rename the files created by the programs to their final names, and return the new paths.
+fn rename_temporary_files(
+    dir: &Path,
+    prefix: &str,
+    fsync: bool,
+) -> Result<Vec<PathBuf>, XcompressError> {
+    let file_paths = get_temporary_files(dir, prefix)?;
+
+    if fsync {
+        for file_path in file_paths.iter() {
+            File::open(file_path).and_then(|file| file.sync_all()).with_path(file_path)?;
+        }
+    }
+
+    let mut new_file_paths = Vec::with_capacity(file_paths.len());
+
+    for file_path in file_paths {
+        let file_name = file_path.file_name().unwrap().as_bytes();
+
+        let new_file_path = dir.join(OsStr::from_bytes(&file_name[prefix.len()..]));
+
+        fs::rename(file_path.as_path(), new_file_path.as_path())
+            .with_path(new_file_path.as_path())?;
+
+        new_file_paths.push(new_file_path);
+    }
+
+    if fsync {
+        // make the renaming durable
+        File::open(dir).and_then(|dir| dir.sync_all()).with_path(dir)?;
+    }
+
+    Ok(new_file_paths)
+}
+
+/// Delete the volumes which follow the last written volume of a split archive, like `foo.tar.zst.004` after `foo.tar.zst.003`, `foo.z03` after `foo.z02` or `foo.part4.rar` after `foo.part3.rar`.
+fn delete_stale_volumes(volume_paths: &[PathBuf]) {
+    for volume_path in volume_paths {
+        let mut next_volume_path = match get_next_volume_path(volume_path.as_path()) {
+            Some(next_volume_path) => next_volume_path,
+            None => continue,
+        };
+
+        if volume_paths.contains(&next_volume_path) {
+            continue;
+        }
+
+        while next_volume_path.is_file() {
+            try_delete_file(next_volume_path.as_path());
+
+            next_volume_path = match get_next_volume_path(next_volume_path.as_path()) {
+                Some(next_volume_path) => next_volume_path,
+                None => break,
+            };
+        }
+    }
+}
+
+#[inline]
+fn delete_temporary_files(dir: &Path, prefix: &str) {
+    if let Ok(file_paths) = get_temporary_files(dir, prefix) {
+        for file_path in file_paths {
+            try_delete_file(file_path);
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn try_delete_file<P: AsRef<Path>>(file_path: P) {
+    if fs::remove_file(file_path).is_err() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+    use crate::test_util::TestDir;
+
+    #[test]
+    fn temporary_prefix() {
+        let temporary_prefix = get_temporary_prefix();
+
+        assert_eq!("a.tar", trim_temporary_prefix(format!("{temporary_prefix}a.tar").as_str()));
+        assert_eq!("a.tar", trim_temporary_prefix("a.tar"));
+        assert_eq!(".xcompress-a.tar", trim_temporary_prefix(".xcompress-a.tar"));
+    }
+
+    #[test]
+    fn tar_7z_password_not_in_arguments() {
+        let dir = TestDir::new("tar-7z-password");
+
+        // the fake 7z records its arguments and creates the archive
+        let p7z_path = dir.create_file(
+            "7z",
+            b"#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/args\"\nfor arg; do case \"$arg\" in *.7z) : > \"$arg\";; esac; done\n",
+        );
+
+        fs::set_permissions(p7z_path.as_path(), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let input_path = dir.create_file("input.txt", b"data");
+
+        let executable_paths = ExecutablePaths {
+            p7z_path: p7z_path.to_string_lossy().into_owned(),
+            ..ExecutablePaths::default()
+        };
+
+        Archiver::new(ArchiveFormat::Tar7z)
+            .password("secret")
+            .quiet(true)
+            .executable_paths(executable_paths)
+            .create(&[input_path], dir.join("output.tar.7z"))
+            .unwrap();
+
+        let args = fs::read_to_string(dir.join("args")).unwrap();
+
+        assert!(args.lines().any(|arg| arg == "-p"));
+        assert!(args.lines().any(|arg| arg.ends_with("output.tar")));
+        assert!(!args.contains("secret"));
+
+        // only the archive is left besides the files of the test, without the temporary TAR file
+        assert!(dir.join("output.tar.7z").exists());
+        assert_eq!(4, fs::read_dir(dir.join("")).unwrap().count());
+    }
+}
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
 use concat_with::concat_line;
 use terminal_size::terminal_size;
+use xcompress::{ArchiveFormat, ExecutablePaths, IoPriority, OverwritePolicy};
 
 const APP_NAME: &str = "XCompress";
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -19,38 +20,57 @@ const APP_ABOUT: &str = concat!(
         "a -o /tmp/out.7z foo.wav       # Archive foo.wav to /tmp/out.7z",
         "a -b foo/bar                   # Archive foo/bar folder to bar.rar as small as possible",
         "a -f foo/bar -r 5              # Archive foo/bar folder to bar.rar as fast as possible and add 5% recovery record",
+        "a --level 6 -o foo.tar.zst bar # Archive bar to foo.tar.zst with the compression level 6 of 9",
         "a -p password foo.wav          # Archive foo.wav to foo.rar with a password",
+        "a --format tgz -o foo.dat bar  # Archive bar to foo.dat in the TAR.GZ format",
+        "a -d 4GiB -o foo.tar.zst bar   # Archive bar to foo.tar.zst.001, foo.tar.zst.002, etc, of 4 GiB each",
         "x foo.rar                      # Extract foo.rar into current working directory",
         "x foo.tar.gz /tmp/out_folder   # Extract foo.tar.gz into /tmp/out_folder",
-        "x -p password foo.rar          # Extract foo.rar with a password into current working directory"
+        "x foo.tar.zst.001              # Extract the split foo.tar.zst into current working directory",
+        "x -p password foo.rar          # Extract foo.rar with a password into current working directory",
+        "x --format rar foo             # Extract foo as a RAR archive into current working directory",
+        "x --overwrite never foo.zip    # Extract foo.zip without replacing existing files",
+        "x -c foo.gz                    # Decompress foo.gz to stdout",
+        "x foo.tar.gz . -- bar/baz.txt  # Extract only bar/baz.txt from foo.tar.gz into current working directory",
+        "x --include '*.yaml' foo.zip   # Extract only the YAML files from foo.zip into current working directory",
+        "x --strip-components 1 foo.zip # Extract foo.zip without its top-level directory into current working directory",
+        "x --smart foo.zip              # Extract foo.zip into foo/ if it has more than one top-level entry",
+        "a -o - --format tar.zst bar    # Archive bar in the TAR.ZST format to stdout",
+        "l foo.tar.xz                   # List the contents of foo.tar.xz",
+        "l --json foo.zip               # List the contents of foo.zip in JSON",
+        "t foo.tar.zst bar.7z           # Test the integrity of foo.tar.zst and bar.7z"
+    ),
+    "\n\nEXIT STATUS:\n",
+    concat_line!(
+        "0      Success",
+        "1      General error",
+        "2      Invalid arguments or paths",
+        "3      The archive file is corrupted",
+        "4      A required program is not found",
+        "5      The password is incorrect",
+        "6      An option is not supported by the archive format",
+        "7      I/O error",
+        "8      A program failed",
+        "9      The archive file contains entries outside the destination",
+        "10     A limit of the extracted data is exceeded",
+        "11     There is not enough free space",
+        "128+N  A program was killed by signal N"
     )
 );
 
-const DEFAULT_COMPRESS_PATH: &str = "compress";
-const DEFAULT_ZIP_PATH: &str = "zip";
-const DEFAULT_UNZIP_PATH: &str = "unzip";
-const DEFAULT_GZIP_PATH: &str = "gzip";
-const DEFAULT_GUNZIP_PATH: &str = "gunzip";
-const DEFAULT_PIGZ_PATH: &str = "pigz";
-const DEFAULT_BZIP2_PATH: &str = "bzip2";
-const DEFAULT_BUNZIP2_PATH: &str = "bunzip2";
-const DEFAULT_LBZIP2_PATH: &str = "lbzip2";
-const DEFAULT_PBZIP2_PATH: &str = "pbzip2";
-const DEFAULT_LZIP_PATH: &str = "lzip";
-const DEFAULT_LUNZIP_PATH: &str = "lunzip";
-const DEFAULT_PLZIP_PATH: &str = "plzip";
-const DEFAULT_XZ_PATH: &str = "xz";
-const DEFAULT_UNXZ_PATH: &str = "unxz";
-const DEFAULT_PXZ_PATH: &str = "pxz";
-const DEFAULT_LZMA_PATH: &str = "lzma";
-const DEFAULT_UNLZMA_PATH: &str = "unlzma";
-const DEFAULT_7Z_PATH: &str = "7z";
-const DEFAULT_TAR_PATH: &str = "tar";
-const DEFAULT_RAR_PATH: &str = "rar";
-const DEFAULT_UNRAR_PATH: &str = "unrar";
-const DEFAULT_ZSTD_PATH: &str = "zstd";
-const DEFAULT_UNZSTD_PATH: &str = "unzstd";
-const DEFAULT_PZSTD_PATH: &str = "pzstd";
+pub const EXIT_CODE_GENERAL: i32 = 1;
+pub const EXIT_CODE_INVALID_ARGUMENTS: i32 = 2;
+pub const EXIT_CODE_CORRUPTED: i32 = 3;
+pub const EXIT_CODE_TOOL_NOT_FOUND: i32 = 4;
+pub const EXIT_CODE_WRONG_PASSWORD: i32 = 5;
+pub const EXIT_CODE_UNSUPPORTED_OPTION: i32 = 6;
+pub const EXIT_CODE_IO: i32 = 7;
+pub const EXIT_CODE_TOOL_FAILED: i32 = 8;
+pub const EXIT_CODE_UNSAFE_ENTRIES: i32 = 9;
+pub const EXIT_CODE_LIMIT_EXCEEDED: i32 = 10;
+pub const EXIT_CODE_INSUFFICIENT_SPACE: i32 = 11;
+/// Added to the signal number if a program is killed by a signal, like shells do.
+pub const EXIT_CODE_SIGNAL_BASE: i32 = 128;
 
 #[derive(Debug, Parser)]
 #[command(name = APP_NAME)]
@@ -69,197 +89,82 @@ pub struct CLIArgs {
 
     #[arg(short, long)]
     #[arg(global = true)]
-    #[arg(help = "Use only one thread")]
+    #[arg(help = "Use only one thread. The same as `--threads 1`")]
     pub single_thread: bool,
 
-    #[arg(short, long)]
+    #[arg(long, value_name = "N")]
     #[arg(global = true)]
-    #[arg(num_args = 0..=1, default_missing_value = "")]
-    #[arg(help = "Set password for your archive file. (Only supports 7Z, ZIP and RAR) Set an \
-                  empty string to read a password from stdin")]
-    pub password: Option<String>,
+    #[arg(env = "XCOMPRESS_THREADS")]
+    #[arg(default_value_t = 0)]
+    #[arg(help = "Set the number of threads which the programs can use. Set 0 to use the number \
+                  of CPUs")]
+    pub threads: usize,
 
     #[command(flatten)]
-    pub executable_paths: ExecutablePaths,
-}
-
-#[derive(Debug, Args)]
-pub struct ExecutablePaths {
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_COMPRESS_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your compress executable binary file")]
-    pub compress_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_ZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your zip executable binary file")]
-    pub zip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_UNZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your unzip executable binary file")]
-    pub unzip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_GZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your gzip executable binary file")]
-    pub gzip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_GUNZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your gunzip executable binary file")]
-    pub gnuzip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_PIGZ_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your pigz executable binary file")]
-    pub pigz_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_BZIP2_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your bzip2 executable binary file")]
-    pub bzip2_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_BUNZIP2_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your bunzip2 executable binary file")]
-    pub bunzip2_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_LBZIP2_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your lbzip2 executable binary file")]
-    pub lbzip2_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_PBZIP2_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your pbzip2 executable binary file")]
-    pub pbzip2_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_LZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your lzip executable binary file")]
-    pub lzip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_LUNZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your lunzip executable binary file")]
-    pub lunzip_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_PLZIP_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your plzip executable binary file")]
-    pub plzip_path: String,
+    pub password_options: PasswordOptions,
 
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_XZ_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your xz executable binary file")]
-    pub xz_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_UNXZ_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your unxz executable binary file")]
-    pub unxz_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_PXZ_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your pxz executable binary file")]
-    pub pxz_path: String,
+    #[command(flatten)]
+    pub resource_options: ResourceOptions,
 
     #[arg(long)]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_LZMA_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your lzma executable binary file")]
-    pub lzma_path: String,
+    #[arg(help = format!("Specify the archive format instead of determining it by the file name \
+                          extension or the file content. Accepted values: {}", ArchiveFormat::NAMES))]
+    pub format: Option<ArchiveFormat>,
 
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_UNLZMA_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your unlzma executable binary file")]
-    pub unlzma_path: String,
+    #[command(flatten)]
+    pub executable_paths: ExecutablePaths,
+}
 
-    #[arg(name = "7z-path", value_name = "7Z_PATH")]
-    #[arg(long)]
+/// The password can also be set by the `XCOMPRESS_PASSWORD` environment variable.
+#[derive(Debug, Args)]
+pub struct PasswordOptions {
+    #[arg(short, long)]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_7Z_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your 7z executable binary file")]
-    pub p7z_path: String,
+    #[arg(num_args = 0..=1, default_missing_value = "")]
+    #[arg(conflicts_with_all = ["password_file", "password_fd"])]
+    #[arg(help = "Set password for your archive file. (Only supports 7Z, ZIP and RAR) Set an \
+                  empty string to enter a password on the terminal without echo. The password \
+                  can also be set by the XCOMPRESS_PASSWORD environment variable")]
+    pub password: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, value_name = "PATH")]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_TAR_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your tar executable binary file")]
-    pub tar_path: String,
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    #[arg(conflicts_with = "password_fd")]
+    #[arg(help = "Read the password from the first line of a file")]
+    pub password_file: Option<PathBuf>,
 
-    #[arg(long)]
+    #[arg(long, value_name = "FD")]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_RAR_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your rar executable binary file")]
-    pub rar_path: String,
-
-    #[arg(long)]
-    #[arg(global = true)]
-    #[arg(default_value = DEFAULT_UNRAR_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your unrar executable binary file")]
-    pub unrar_path: String,
+    #[arg(help = "Read the password from the first line of a file descriptor")]
+    pub password_fd: Option<i32>,
+}
 
-    #[arg(long)]
+#[derive(Debug, Args)]
+pub struct ResourceOptions {
+    #[arg(long, value_name = "SIZE")]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_ZSTD_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your zstd executable binary file")]
-    pub zstd_path: String,
+    #[arg(help = "Limit the memory usage of xz, lzma, zstd and 7z. The unit of value is byte. \
+                  You can also use KB, MB, KiB, MiB, etc, as a suffix. xz reduces the \
+                  compression settings to fit in the limit, and the decompression fails if it \
+                  needs more memory. 7z needs to be 7-Zip 22.00 or later")]
+    pub memory_limit: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, value_name = "N")]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_UNZSTD_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your unzstd executable binary file")]
-    pub unzstd_path: String,
+    #[arg(allow_negative_numbers = true)]
+    #[arg(value_parser = clap::value_parser!(i32).range(-20..=19))]
+    #[arg(help = "Run the programs with a niceness from -20 (the highest priority) to 19 (the \
+                  lowest priority)")]
+    pub nice: Option<i32>,
 
-    #[arg(long)]
+    #[arg(long, value_name = "CLASS[:LEVEL]")]
     #[arg(global = true)]
-    #[arg(default_value = DEFAULT_PZSTD_PATH)]
-    #[arg(value_hint = clap::ValueHint::CommandName)]
-    #[arg(help = "Specify the path of your pzstd executable binary file")]
-    pub pzstd_path: String,
+    #[arg(help = "Run the programs with an I/O scheduling class and a level from 0 (the highest \
+                  priority) to 7 (the lowest priority). Accepted classes: idle, best-effort, \
+                  realtime")]
+    pub ionice: Option<IoPriority>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -268,19 +173,70 @@ pub enum CLICommands {
     #[command(after_help = AFTER_HELP)]
     X {
         #[arg(value_hint = clap::ValueHint::FilePath)]
-        #[arg(
-            help = "Assign the source of your original files. It should be at least one file path"
-        )]
-        input_path:  PathBuf,
+        #[arg(help = "Assign the source of your original files. It should be at least one file \
+                      path. Use `-` to read the archive from stdin")]
+        input_path:       PathBuf,
         #[arg(value_hint = clap::ValueHint::DirPath)]
-        #[arg(conflicts_with = "output")]
+        #[arg(conflicts_with_all = ["output", "to_stdout"])]
         #[arg(help = "Assign a destination of your extracted files. It should be a directory path")]
-        output_path: Option<PathBuf>,
+        output_path:      Option<PathBuf>,
         #[arg(short, long)]
         #[arg(value_hint = clap::ValueHint::DirPath)]
-        #[arg(conflicts_with = "output_path")]
+        #[arg(conflicts_with_all = ["output_path", "to_stdout"])]
         #[arg(help = "Assign a destination of your extracted files. It should be a directory path")]
-        output:      Option<PathBuf>,
+        output:           Option<PathBuf>,
+        #[arg(short = 'c', long)]
+        #[arg(help = "Decompress a single-file format (Z, GZIP, BZIP2, LZ, XZ, LZMA or ZSTD) to \
+                      stdout, like `zcat`")]
+        to_stdout:        bool,
+        #[arg(long, value_name = "PATTERN")]
+        #[arg(help = "Only extract the entries matching a glob pattern, like `config/*.yaml`. \
+                      It can be used multiple times")]
+        include:          Vec<String>,
+        #[arg(long, value_name = "PATTERN")]
+        #[arg(help = "Do not extract the entries matching a glob pattern. It can be used \
+                      multiple times")]
+        exclude:          Vec<String>,
+        #[arg(long, value_name = "N")]
+        #[arg(help = "Remove N leading components from the paths of the extracted entries, like \
+                      `tar --strip-components`. The entries with fewer components are not \
+                      extracted")]
+        strip_components: Option<usize>,
+        #[arg(long)]
+        #[arg(env = "XCOMPRESS_SMART")]
+        #[arg(help = "Extract the archive file into a new directory named after it, like `foo` \
+                      or `foo (1)`, unless all entries are in a single top-level directory. Set \
+                      XCOMPRESS_SMART=true to make it the default")]
+        smart:            bool,
+        #[arg(long, value_name = "POLICY")]
+        #[arg(default_value = "always")]
+        #[arg(help = "Decide what to do if an extracted file already exists. Accepted values: \
+                      always, never, rename (extract it as `foo_1.txt`), newer (replace it only \
+                      if it is older), ask. For 7Z, ZIP and RAR, `ask` is answered to 7z, unzip \
+                      and unrar themselves, so it cannot be used with --password")]
+        overwrite:        OverwritePolicy,
+        #[arg(long)]
+        #[arg(help = "Extract the archive file even if it contains absolute paths, `..` \
+                      components, or links pointing outside the destination")]
+        unsafe_paths:     bool,
+        #[arg(long, value_name = "SIZE")]
+        #[arg(help = "Abort if the extracted data is larger than a specified size. The unit of \
+                      value is byte. You can also use KB, MB, KiB, MiB, etc, as a suffix")]
+        max_output_size:  Option<String>,
+        #[arg(long, value_name = "RATIO")]
+        #[arg(help = "Abort if the extracted data is larger than a specified multiple of the \
+                      size of the archive file")]
+        max_ratio:        Option<f64>,
+        #[arg(long, value_name = "N")]
+        #[arg(help = "Abort if the archive file contains more than a specified number of entries")]
+        max_entries:      Option<u64>,
+        #[arg(long)]
+        #[arg(help = "Do not check whether there is enough free space for the extracted data")]
+        skip_space_check: bool,
+        #[arg(last = true, value_name = "MEMBERS")]
+        #[arg(help = "Only extract these entries, which are given after `--`. A directory \
+                      includes the entries in it")]
+        members:          Vec<String>,
     },
     #[command(about = "Add files to archive. Excludes base directory from names (e.g. add \
                        /path/to/folder, you can always get the \"folder\" in the root of the \
@@ -289,15 +245,15 @@ pub enum CLICommands {
     A {
         #[arg(required = true)]
         #[arg(value_hint = clap::ValueHint::AnyPath)]
-        #[arg(
-            help = "Assign the source of your original files. It should be at least one file path"
-        )]
+        #[arg(help = "Assign the source of your original files. It should be at least one file \
+                      path. Use `-` to read the data to compress from stdin")]
         input_paths:         Vec<PathBuf>,
         #[arg(short, long)]
         #[arg(value_hint = clap::ValueHint::FilePath)]
         #[arg(help = "Assign a destination of your extracted files. It should be a file path. \
                       Specify the file extension name in order to determine which archive \
-                      format you want to use. [default archive format: RAR]")]
+                      format you want to use. Use `-` to write the archive to stdout. [default \
+                      archive format: RAR]")]
         output_path:         Option<PathBuf>,
         #[arg(short, long, visible_alias = "best")]
         #[arg(conflicts_with = "fastest_compression")]
@@ -311,15 +267,81 @@ pub enum CLICommands {
                       to get the fastest compression and depression time, it will make the \
                       compression as minimal as possible (even not use compression at all)")]
         fastest_compression: bool,
+        #[arg(long, value_name = "N")]
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=9))]
+        #[arg(conflicts_with_all = ["best_compression", "fastest_compression", "raw_level"])]
+        #[arg(help = "Set the compression level from 0 (the fastest) to 9 (the smallest). It is \
+                      mapped to gzip -1~-9 (pigz -11 for 9), bzip2 -1~-9, lzip -0~-9, xz -0~-9 \
+                      (with -e for 9), zstd -1~-19 (--ultra -22 for 9), rar -m0~-m5, 7z \
+                      -mx0~-mx9 and zip -0~-9")]
+        level:               Option<u8>,
+        #[arg(long, value_name = "N")]
+        #[arg(conflicts_with_all = ["best_compression", "fastest_compression"])]
+        #[arg(help = "Pass a compression level to the program directly, e.g. 19 for zstd or 5 \
+                      for rar")]
+        raw_level:           Option<u8>,
         #[arg(short = 'd', long)]
         #[arg(help = "Split the archive file into volumes with a specified size. The unit of \
                       value is byte. You can also use KB, MB, KiB, MiB, etc, as a suffix. The \
-                      minimum volume is 64 KiB (Only supports 7Z, ZIP and RAR)")]
+                      minimum volume is 64 KiB. The volumes of the formats other than ZIP and \
+                      RAR are named like foo.tar.zst.001, foo.tar.zst.002, etc")]
         split:               Option<String>,
+        #[arg(long, value_name = "SIZE")]
+        #[arg(help = "Set the dictionary size. The unit of value is byte. You can also use KB, \
+                      MB, KiB, MiB, etc, as a suffix (Only supports 7Z, XZ, LZMA and RAR)")]
+        dict_size:           Option<String>,
+        #[arg(long, value_name = "N")]
+        #[arg(value_parser = clap::value_parser!(u8).range(10..=31))]
+        #[arg(help = "Enable the long distance matching with a window of 2^N bytes. The window \
+                      larger than 2^27 needs `--long` when decompressed by zstd itself (Only \
+                      supports ZSTD)")]
+        long_window:         Option<u8>,
+        #[arg(long)]
+        #[arg(help = "Compress the files as a continuous stream (Only supports 7Z and RAR)")]
+        solid:               bool,
+        #[arg(long, value_name = "SIZE")]
+        #[arg(help = "Set the size of each solid block. The unit of value is byte. You can also \
+                      use KB, MB, KiB, MiB, etc, as a suffix (Only supports 7Z)")]
+        solid_block_size:    Option<String>,
+        #[arg(long)]
+        #[arg(help = "Make the archive file friendly to rsync (Only supports GZIP and ZSTD)")]
+        rsyncable:           bool,
         #[arg(short, long = "recovery-record", visible_alias = "rr")]
         #[arg(value_parser = clap::value_parser!(u8).range(1..=100))]
         #[arg(help = "Add data recovery record (Only supports RAR)")]
         recovery_record:     Option<u8>,
+        #[arg(long)]
+        #[arg(help = "Treat files which are changed while being archived as a warning instead \
+                      of an error (Only affects the formats archived by tar)")]
+        allow_changed_files: bool,
+        #[arg(long)]
+        #[arg(help = "Flush the archive file to the disk before it is moved to the destination")]
+        fsync:               bool,
+        #[arg(long)]
+        #[arg(help = "Do not check whether there is enough free space for the archive file")]
+        skip_space_check:    bool,
+    },
+    #[command(about = "List the contents of an archive file")]
+    #[command(after_help = AFTER_HELP)]
+    L {
+        #[arg(value_hint = clap::ValueHint::FilePath)]
+        #[arg(help = "Assign the archive file you want to list. Use `-` to read it from stdin")]
+        input_path: PathBuf,
+        #[arg(long)]
+        #[arg(help = "Print the entries in JSON")]
+        json:       bool,
+    },
+    #[command(about = "Test the integrity of archive files. The exit status is 3 if an archive \
+                       file is corrupted, 4 if a required program is not found, or 5 if the \
+                       password is incorrect")]
+    #[command(after_help = AFTER_HELP)]
+    T {
+        #[arg(required = true)]
+        #[arg(value_hint = clap::ValueHint::FilePath)]
+        #[arg(
+            help = "Assign the archive files you want to test. It should be at least one file path"
+        )]
+        input_paths: Vec<PathBuf>,
     },
 }
 
@@ -333,7 +355,13 @@ pub fn get_args() -> CLIArgs {
     let matches = args.get_matches();
 
     match CLIArgs::from_arg_matches(&matches) {
-        Ok(args) => args,
+        Ok(mut args) => {
+            if args.single_thread {
+                args.threads = 1;
+            }
+
+            args
+        },
         Err(err) => {
             err.exit();
         },
@@ -0,0 +1,851 @@
+use std::{
+    env,
+    fs::File,
+    io,
+    io::{Read, Write},
+    mem::MaybeUninit,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::{
+            fs::PermissionsExt,
+            process::{CommandExt, ExitStatusExt},
+        },
+    },
+    path::Path,
+    process::{Child, ChildStderr, Command, ExitStatus, Output, Stdio},
+    ptr,
+    sync::Mutex,
+    thread,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use execute::{command_args, Execute};
+
+use crate::XcompressError;
+
+/// How many bytes at the end of stderr are kept for the error message.
+const STDERR_BUFFER_SIZE: usize = 4096;
+
+/// How many lines at the end of stderr are put in the error message.
+const STDERR_TAIL_LINES: usize = 10;
+
+const SIGPIPE: i32 = 13;
+
+/// How often the pseudo terminal for typing a password is checked, in milliseconds.
+const PASSWORD_POLL_INTERVAL: libc::c_int = 20;
+
+/// How long to wait before typing the password again, if the echo is still turned off.
+const PASSWORD_RETYPE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many times the password can be typed, which is enough for a prompt and its confirmation with some retries. The pseudo terminal is closed after that, so a program asking forever fails instead of hanging.
+const PASSWORD_MAX_TYPED: usize = 5;
+
+/// How often a watchdog is called while programs are running.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The path which means stdin or stdout, like most programs.
+pub(crate) const STDIO_PATH: &str = "-";
+
+#[inline]
+pub(crate) fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO_PATH
+}
+
+/// Create a pipe whose read end can be stdin of a program. The data of the reader is written to the pipe by another thread, which stops when the program stops reading.
+pub(crate) fn create_input_pipe<R: Read + Send + 'static>(mut reader: R) -> io::Result<Stdio> {
+    let mut fds: [libc::c_int; 2] = [-1; 2];
+
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let reader_fd = unsafe { OwnedFd::from_raw_fd(fds[0]) };
+    let writer_fd = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+
+    thread::spawn(move || {
+        let mut writer = File::from(writer_fd);
+
+        // the program may stop reading before the end
+        if io::copy(&mut reader, &mut writer).is_err() {}
+    });
+
+    Ok(Stdio::from(reader_fd))
+}
+
+/// Make the command read the password from a pseudo terminal, which becomes its controlling terminal and stdin, so that the password does not appear in its arguments. The password is typed whenever the echo of the terminal is turned off, like a password prompt does.
+///
+/// Return `false` if a pseudo terminal cannot be opened. The program must be run without other input from stdin.
+pub(crate) fn type_password(command: &mut Command, password: &str) -> bool {
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+
+    if unsafe { libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), ptr::null()) }
+        != 0
+    {
+        return false;
+    }
+
+    let master = unsafe { OwnedFd::from_raw_fd(master) };
+    let slave = unsafe { OwnedFd::from_raw_fd(slave) };
+
+    // other children must not inherit the pseudo terminal
+    for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+        unsafe {
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+
+    command.stdin(Stdio::from(slave));
+
+    unsafe {
+        command.pre_exec(|| {
+            // stdin is the slave now, make it the controlling terminal of a new session
+            if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+
+    let line = format!("{password}\n");
+
+    thread::spawn(move || {
+        let mut master = File::from(master);
+        let mut buffer = [0u8; 1024];
+        let mut typed_at: Option<Instant> = None;
+        let mut typed_count = 0;
+
+        loop {
+            let mut poll_fd =
+                libc::pollfd {
+                    fd: master.as_raw_fd(), events: libc::POLLIN, revents: 0
+                };
+
+            let n = unsafe { libc::poll(&mut poll_fd, 1, PASSWORD_POLL_INTERVAL) };
+
+            if n < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+
+                break;
+            }
+
+            if n > 0 {
+                // the prompts are discarded, and the terminal is closed if the program exits
+                if poll_fd.revents & libc::POLLIN != 0 {
+                    if matches!(master.read(&mut buffer), Ok(0) | Err(_)) {
+                        break;
+                    }
+                } else {
+                    break;
+                }
+            }
+
+            let mut termios = MaybeUninit::<libc::termios>::uninit();
+
+            // the attributes of the slave are got from the master
+            if unsafe { libc::tcgetattr(master.as_raw_fd(), termios.as_mut_ptr()) } != 0 {
+                break;
+            }
+
+            let termios = unsafe { termios.assume_init() };
+
+            if termios.c_lflag & libc::ECHO != 0 {
+                typed_at = None;
+
+                continue;
+            }
+
+            // type again if the password seems to be flushed, or is asked for confirmation
+            if typed_at.map(|t| t.elapsed() >= PASSWORD_RETYPE_INTERVAL).unwrap_or(true) {
+                if typed_count == PASSWORD_MAX_TYPED || master.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+
+                typed_at = Some(Instant::now());
+                typed_count += 1;
+            }
+        }
+    });
+
+    true
+}
+
+/// Run a check of a program only once, since its version does not change. The results are cached for each program.
+fn check_program_once(
+    cache: &Mutex<Vec<(String, bool)>>,
+    program: &str,
+    check: impl FnOnce() -> bool,
+) -> bool {
+    let mut cache = cache.lock().unwrap();
+
+    if let Some((_, supported)) = cache.iter().find(|(cached_program, _)| cached_program == program)
+    {
+        return *supported;
+    }
+
+    let supported = check();
+
+    cache.push((program.to_string(), supported));
+
+    supported
+}
+
+/// Parse a version like `5.4.1` or `16.02` into its major and minor numbers.
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.split('.').map(|number| number.parse::<u32>());
+
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => Some((major, minor)),
+        _ => None,
+    }
+}
+
+/// Check whether xz can decompress with multiple threads, which is supported since XZ Utils 5.4.
+pub(crate) fn supports_xz_parallel_decompression(program: &str) -> bool {
+    static CACHE: Mutex<Vec<(String, bool)>> = Mutex::new(Vec::new());
+
+    check_program_once(&CACHE, program, || {
+        let mut command = command_args!(program, "--version");
+
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::null());
+
+        // e.g. `xz (XZ Utils) 5.4.1`
+        command
+            .execute_output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                parse_version(stdout.lines().next()?.split_whitespace().last()?)
+            })
+            .is_some_and(|version| version >= (5, 4))
+    })
+}
+
+/// Check whether 7z accepts `-mmemuse`, which is supported since 7-Zip 22.00. p7zip, which is 16.02, rejects it.
+pub(crate) fn supports_7z_memory_limit(program: &str) -> bool {
+    static CACHE: Mutex<Vec<(String, bool)>> = Mutex::new(Vec::new());
+
+    check_program_once(&CACHE, program, || {
+        let mut command = command_args!(program);
+
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::null());
+
+        // e.g. `7-Zip (a) 23.01 (x64) : Copyright (c) 1999-2023 Igor Pavlov : 2023-06-20`
+        command
+            .execute_output()
+            .ok()
+            .and_then(|output| {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                let line = stdout.lines().find(|line| line.contains("7-Zip"))?;
+
+                line.split_whitespace().find_map(parse_version)
+            })
+            .is_some_and(|version| version >= (22, 0))
+    })
+}
+
+/// Check whether a program can be executed, by running it with an argument which makes it exit successfully, like `-V`. The argument is always the same for a program, so only the program is cached.
+pub(crate) fn is_executable(program: &str, arg: &str) -> bool {
+    static CACHE: Mutex<Vec<(String, bool)>> = Mutex::new(Vec::new());
+
+    check_program_once(&CACHE, program, || {
+        command_args!(program, arg).execute_check_exit_status_code(0).is_ok()
+    })
+}
+
+/// Check whether a program is an executable file, at its path or in `PATH`, for the programs which have no argument to exit successfully, like pixz.
+pub(crate) fn is_executable_file(program: &str) -> bool {
+    let check = |path: &Path| {
+        path.metadata()
+            .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+
+    if program.contains('/') {
+        return check(Path::new(program));
+    }
+
+    match env::var_os("PATH") {
+        Some(paths) => env::split_paths(&paths).any(|dir| check(dir.join(program).as_path())),
+        None => false,
+    }
+}
+
+#[inline]
+pub(crate) fn get_program_name(command: &Command) -> String {
+    command.get_program().to_string_lossy().into_owned()
+}
+
+#[inline]
+fn map_spawn_error(command: &Command, error: io::Error) -> XcompressError {
+    if error.kind() == io::ErrorKind::NotFound {
+        XcompressError::ToolNotFound(get_program_name(command))
+    } else {
+        XcompressError::Io {
+            path: None, source: error
+        }
+    }
+}
+
+/// Read stderr of a child process in another thread, to prevent the child from being blocked. The data is forwarded to the stderr of this process unless `quiet` is true, and the end of it is kept.
+fn spawn_stderr_reader(mut stderr: ChildStderr, quiet: bool) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+
+        loop {
+            match stderr.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(c) => {
+                    if !quiet {
+                        let _ = io::stderr().write_all(&chunk[..c]);
+                    }
+
+                    buffer.extend_from_slice(&chunk[..c]);
+
+                    if buffer.len() > STDERR_BUFFER_SIZE * 2 {
+                        buffer.drain(..buffer.len() - STDERR_BUFFER_SIZE);
+                    }
+                },
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(_) => break,
+            }
+        }
+
+        buffer
+    })
+}
+
+/// Get the last lines of stderr.
+fn get_stderr_tail(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+
+    let lines: Vec<&str> = stderr.lines().filter(|line| !line.trim().is_empty()).collect();
+
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+}
+
+/// Whether a program failed because of an incorrect password.
+fn is_wrong_password(command: &Command, status: ExitStatus, stderr: &[u8]) -> bool {
+    let program = get_program_name(command);
+    let program_name = Path::new(program.as_str()).file_name().map(|name| name.to_string_lossy());
+
+    // unzip exits with 82 and unrar exits with 11 if the password is incorrect, but the other programs use these codes for other things
+    let code = match program_name.as_deref() {
+        Some("unzip") => Some(82),
+        Some("unrar") | Some("rar") => Some(11),
+        _ => None,
+    };
+
+    if code.is_some() && status.code() == code {
+        return true;
+    }
+
+    let stderr = String::from_utf8_lossy(stderr).to_ascii_lowercase();
+
+    stderr.contains("wrong password") || stderr.contains("incorrect password")
+}
+
+/// xz, zstd and 7z refuse to decompress the data which needs more memory than the limit.
+fn is_memory_limit_exceeded(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr).to_ascii_lowercase();
+
+    stderr.contains("memory usage limit")
+        || stderr.contains("requires too much memory")
+        || stderr.contains("window size larger than maximum")
+}
+
+/// Turn an unsuccessful exit status into an error. Some programs use a non-zero exit code for warnings, so they can be accepted additionally.
+fn check_status(
+    command: &Command,
+    status: ExitStatus,
+    stderr: &[u8],
+    accepted_codes: &[i32],
+) -> Result<(), XcompressError> {
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) if accepted_codes.contains(&code) => Ok(()),
+        _ if is_wrong_password(command, status, stderr) => Err(XcompressError::WrongPassword),
+        _ if is_memory_limit_exceeded(stderr) => Err(XcompressError::LimitExceeded("memory usage")),
+        code => Err(XcompressError::ToolFailed {
+            program: get_program_name(command),
+            code,
+            signal: status.signal(),
+            stderr_tail: get_stderr_tail(stderr),
+        }),
+    }
+}
+
+/// Run a command. Its stdin and stdout are inherited unless they have been set. Its stderr is captured for the error message, and forwarded unless `quiet` is true.
+#[inline]
+pub(crate) fn run(command: &mut Command, quiet: bool) -> Result<(), XcompressError> {
+    run_accepting(command, quiet, &[])
+}
+
+/// Run a command, and treat the accepted non-zero exit codes as success.
+#[inline]
+pub(crate) fn run_accepting(
+    command: &mut Command,
+    quiet: bool,
+    accepted_codes: &[i32],
+) -> Result<(), XcompressError> {
+    run_output_accepting(command, quiet, accepted_codes).map(|_| ())
+}
+
+/// Run a command and collect its stdout if it is piped.
+#[inline]
+pub(crate) fn run_output(command: &mut Command, quiet: bool) -> Result<Output, XcompressError> {
+    run_output_accepting(command, quiet, &[])
+}
+
+fn run_output_accepting(
+    command: &mut Command,
+    quiet: bool,
+    accepted_codes: &[i32],
+) -> Result<Output, XcompressError> {
+    command.stderr(Stdio::piped());
+
+    let result = command.spawn();
+
+    // release the stdin held by the command, such as the slave of a pseudo terminal, or it is never closed after the child exits
+    command.stdin(Stdio::null());
+
+    let mut child = result.map_err(|error| map_spawn_error(command, error))?;
+
+    let stderr_reader = spawn_stderr_reader(child.stderr.take().unwrap(), quiet);
+
+    let mut output = child.wait_with_output()?;
+
+    output.stderr = stderr_reader.join().unwrap();
+
+    check_status(command, output.status, &output.stderr, accepted_codes)?;
+
+    Ok(output)
+}
+
+/// The children and the threads reading their stderr.
+type SpawnedPipeline = (Vec<Child>, Vec<JoinHandle<Vec<u8>>>);
+
+/// Spawn commands by piping the stdout of each one to the stdin of the next one. The stdout of the last one is untouched.
+fn spawn_pipeline(
+    commands: &mut [&mut Command],
+    quiet: bool,
+) -> Result<SpawnedPipeline, XcompressError> {
+    let last_index = commands.len() - 1;
+
+    let mut children: Vec<Child> = Vec::with_capacity(commands.len());
+    let mut stderr_readers = Vec::with_capacity(commands.len());
+
+    for (i, command) in commands.iter_mut().enumerate() {
+        if let Some(previous) = children.last_mut() {
+            command.stdin(previous.stdout.take().unwrap());
+        }
+
+        if i < last_index {
+            command.stdout(Stdio::piped());
+        }
+
+        command.stderr(Stdio::piped());
+
+        let result = command.spawn();
+
+        // release the read end of the previous pipe held by the command, or the previous child cannot get SIGPIPE if this one exits early
+        command.stdin(Stdio::null());
+
+        match result {
+            Ok(mut child) => {
+                stderr_readers.push(spawn_stderr_reader(child.stderr.take().unwrap(), quiet));
+                children.push(child);
+            },
+            Err(error) => {
+                kill_children(children);
+
+                return Err(map_spawn_error(command, error));
+            },
+        }
+    }
+
+    Ok((children, stderr_readers))
+}
+
+#[inline]
+fn kill_children(children: Vec<Child>) {
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Check the exit status of every command in a pipeline, and pick the error which is most likely the cause.
+fn check_statuses(
+    commands: &[&mut Command],
+    statuses: Vec<ExitStatus>,
+    stderrs: &[Vec<u8>],
+    accepted_codes: &[&[i32]],
+) -> Result<(), XcompressError> {
+    let mut errors = Vec::new();
+
+    for (i, (status, stderr)) in statuses.into_iter().zip(stderrs.iter()).enumerate() {
+        let accepted_codes = accepted_codes.get(i).copied().unwrap_or(&[]);
+
+        if let Err(error) = check_status(commands[i], status, stderr, accepted_codes) {
+            errors.push((status, error));
+        }
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    // a wrong password makes the other commands fail too
+    if let Some(index) =
+        errors.iter().position(|(_, error)| matches!(error, XcompressError::WrongPassword))
+    {
+        return Err(errors.swap_remove(index).1);
+    }
+
+    // a command killed by SIGPIPE is just the victim of a failed command after it
+    let index = errors.iter().position(|(status, _)| status.signal() != Some(SIGPIPE)).unwrap_or(0);
+
+    Err(errors.swap_remove(index).1)
+}
+
+/// Run commands by piping the stdout of each one to the stdin of the next one. The stdout of the last one is collected if it is piped.
+///
+/// The exit status of every command is checked. `accepted_codes[i]` are the non-zero exit codes treated as success for the `i`-th command, and can be omitted for the rest commands.
+pub(crate) fn run_pipeline(
+    commands: &mut [&mut Command],
+    quiet: bool,
+    accepted_codes: &[&[i32]],
+) -> Result<Output, XcompressError> {
+    let (mut children, stderr_readers) = spawn_pipeline(commands, quiet)?;
+
+    let last_child = children.pop().unwrap();
+
+    let mut output = last_child.wait_with_output()?;
+
+    let mut statuses = Vec::with_capacity(commands.len());
+
+    for mut child in children {
+        statuses.push(child.wait()?);
+    }
+
+    statuses.push(output.status);
+
+    let mut stderrs: Vec<Vec<u8>> =
+        stderr_readers.into_iter().map(|stderr_reader| stderr_reader.join().unwrap()).collect();
+
+    check_statuses(commands, statuses, &stderrs, accepted_codes)?;
+
+    output.stderr = stderrs.pop().unwrap();
+
+    Ok(output)
+}
+
+/// Like `run_pipeline`, but the `watchdog` is called periodically while the commands are running, and once after they exit. If it returns an error, the commands are killed and the error is returned. The stdout of the last command must not be piped.
+pub(crate) fn run_pipeline_watched(
+    commands: &mut [&mut Command],
+    quiet: bool,
+    accepted_codes: &[&[i32]],
+    watchdog: &mut dyn FnMut() -> Result<(), XcompressError>,
+) -> Result<(), XcompressError> {
+    let (mut children, stderr_readers) = spawn_pipeline(commands, quiet)?;
+
+    let mut statuses: Vec<Option<ExitStatus>> = vec![None; children.len()];
+
+    loop {
+        for (child, status) in children.iter_mut().zip(statuses.iter_mut()) {
+            if status.is_none() {
+                *status = child.try_wait()?;
+            }
+        }
+
+        let exited = statuses.iter().all(|status| status.is_some());
+
+        if let Err(error) = watchdog() {
+            kill_children(children);
+
+            for stderr_reader in stderr_readers {
+                let _ = stderr_reader.join();
+            }
+
+            return Err(error);
+        }
+
+        if exited {
+            break;
+        }
+
+        thread::sleep(WATCHDOG_INTERVAL);
+    }
+
+    let stderrs: Vec<Vec<u8>> =
+        stderr_readers.into_iter().map(|stderr_reader| stderr_reader.join().unwrap()).collect();
+
+    check_statuses(
+        commands,
+        statuses.into_iter().map(Option::unwrap).collect(),
+        &stderrs,
+        accepted_codes,
+    )
+}
+
+/// Like `run_pipeline`, but the data piped into the command at `index` passes through this process, and the `inspector` is called with every chunk before it is passed on. The data comes from the stdout of the previous command, or from the `reader` if `index` is `0`. If the inspector returns an error, the commands are killed and the error is returned. The stdout of the last command must not be piped.
+pub(crate) fn run_pipeline_inspected(
+    commands: &mut [&mut Command],
+    index: usize,
+    reader: Option<&mut dyn Read>,
+    quiet: bool,
+    accepted_codes: &[&[i32]],
+    inspector: &mut dyn FnMut(&[u8]) -> Result<(), XcompressError>,
+) -> Result<(), XcompressError> {
+    let (sources, sinks) = commands.split_at_mut(index);
+
+    let (mut children, mut stderr_readers) = if sources.is_empty() {
+        (Vec::new(), Vec::new())
+    } else {
+        sources.last_mut().unwrap().stdout(Stdio::piped());
+
+        spawn_pipeline(sources, quiet)?
+    };
+
+    sinks[0].stdin(Stdio::piped());
+
+    let (sink_children, sink_stderr_readers) = match spawn_pipeline(sinks, quiet) {
+        Ok(spawned) => spawned,
+        Err(error) => {
+            kill_children(children);
+
+            return Err(error);
+        },
+    };
+
+    children.extend(sink_children);
+    stderr_readers.extend(sink_stderr_readers);
+
+    let mut stdin = children[index].stdin.take().unwrap();
+
+    let mut child_stdout = None;
+
+    let source: &mut dyn Read = match reader {
+        Some(reader) => reader,
+        None => child_stdout.insert(children[index - 1].stdout.take().unwrap()),
+    };
+
+    let mut buffer = vec![0u8; 65536];
+
+    let result = loop {
+        let c = match source.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(c) => c,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => break Err(error.into()),
+        };
+
+        if let Err(error) = inspector(&buffer[..c]) {
+            break Err(error);
+        }
+
+        // the command exits early, and its exit status tells why
+        if stdin.write_all(&buffer[..c]).is_err() {
+            break Ok(());
+        }
+    };
+
+    if let Err(error) = result {
+        // killed before the pipe is closed, or the commands may complain about the cut data
+        kill_children(children);
+
+        for stderr_reader in stderr_readers {
+            let _ = stderr_reader.join();
+        }
+
+        return Err(error);
+    }
+
+    // close both ends, so that the commands can finish or get SIGPIPE
+    drop(stdin);
+    drop(child_stdout);
+
+    let mut statuses = Vec::with_capacity(children.len());
+
+    for mut child in children {
+        statuses.push(child.wait()?);
+    }
+
+    let stderrs: Vec<Vec<u8>> =
+        stderr_readers.into_iter().map(|stderr_reader| stderr_reader.join().unwrap()).collect();
+
+    check_statuses(commands, statuses, &stderrs, accepted_codes)
+}
+
+/// Run a command and copy its stdout to the writer. The `watchdog` is called with the number of bytes copied so far, after every chunk. If it returns an error, the command is killed and the error is returned.
+#[inline]
+pub(crate) fn run_to_writer(
+    command: &mut Command,
+    quiet: bool,
+    writer: &mut dyn Write,
+    watchdog: &mut dyn FnMut(u64) -> Result<(), XcompressError>,
+) -> Result<(), XcompressError> {
+    run_pipeline_to_writer(&mut [command], quiet, &[], writer, watchdog)
+}
+
+/// Like `run_pipeline`, but the stdout of the last command is copied to the writer, and the `watchdog` is called like `run_to_writer`.
+pub(crate) fn run_pipeline_to_writer(
+    commands: &mut [&mut Command],
+    quiet: bool,
+    accepted_codes: &[&[i32]],
+    writer: &mut dyn Write,
+    watchdog: &mut dyn FnMut(u64) -> Result<(), XcompressError>,
+) -> Result<(), XcompressError> {
+    commands.last_mut().unwrap().stdout(Stdio::piped());
+
+    let (mut children, stderr_readers) = spawn_pipeline(commands, quiet)?;
+
+    let mut stdout = children.last_mut().unwrap().stdout.take().unwrap();
+
+    let mut buffer = vec![0u8; 65536];
+    let mut total = 0u64;
+
+    let result = loop {
+        let c = match stdout.read(&mut buffer) {
+            Ok(0) => break writer.flush().map_err(XcompressError::from),
+            Ok(c) => c,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => break Err(error.into()),
+        };
+
+        total += c as u64;
+
+        if let Err(error) = watchdog(total) {
+            break Err(error);
+        }
+
+        if let Err(error) = writer.write_all(&buffer[..c]) {
+            break Err(error.into());
+        }
+    };
+
+    if let Err(error) = result {
+        kill_children(children);
+
+        for stderr_reader in stderr_readers {
+            let _ = stderr_reader.join();
+        }
+
+        return Err(error);
+    }
+
+    let mut statuses = Vec::with_capacity(children.len());
+
+    for mut child in children {
+        statuses.push(child.wait()?);
+    }
+
+    let stderrs: Vec<Vec<u8>> =
+        stderr_readers.into_iter().map(|stderr_reader| stderr_reader.join().unwrap()).collect();
+
+    check_statuses(commands, statuses, &stderrs, accepted_codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    #[test]
+    fn version() {
+        assert_eq!(Some((5, 4)), parse_version("5.4.1"));
+        assert_eq!(Some((16, 2)), parse_version("16.02"));
+        assert_eq!(None, parse_version("5"));
+        assert_eq!(None, parse_version("(x64)"));
+        assert_eq!(None, parse_version("v5.4"));
+    }
+
+    #[test]
+    fn typed_password() {
+        let mut command = Command::new("sh");
+
+        command.args(["-c", "stty -echo; read -r line; printf %s \"$line\"; stty echo"]);
+        command.stdout(Stdio::piped());
+
+        assert!(type_password(&mut command, "secret"));
+
+        let output = run_output(&mut command, true).unwrap();
+
+        assert_eq!(b"secret", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn typed_password_bounded() {
+        // the pseudo terminal is closed after the password is typed enough times, so a program asking forever is hung up
+        let mut command = Command::new("sh");
+
+        command.args(["-c", "stty -echo; while read -r line; do :; done"]);
+
+        assert!(type_password(&mut command, "secret"));
+
+        assert!(matches!(
+            run(&mut command, true),
+            Err(XcompressError::ToolFailed {
+                signal: Some(libc::SIGHUP),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn executable_file() {
+        assert!(is_executable_file("sh"));
+        assert!(is_executable_file("/bin/sh"));
+        assert!(!is_executable_file("/"));
+        assert!(!is_executable_file("xcompress-no-such-program"));
+    }
+
+    #[test]
+    fn wrong_password() {
+        let exit_status = |code: i32| ExitStatus::from_raw(code << 8);
+
+        assert!(is_wrong_password(&Command::new("unzip"), exit_status(82), b""));
+        assert!(is_wrong_password(&Command::new("/usr/bin/unrar"), exit_status(11), b""));
+        assert!(is_wrong_password(&Command::new("rar"), exit_status(11), b""));
+
+        // the codes of unzip and unrar mean other things for the other programs
+        assert!(!is_wrong_password(&Command::new("unzip"), exit_status(11), b""));
+        assert!(!is_wrong_password(&Command::new("7z"), exit_status(82), b""));
+        assert!(!is_wrong_password(&Command::new("tar"), exit_status(11), b""));
+
+        assert!(is_wrong_password(
+            &Command::new("7z"),
+            exit_status(2),
+            b"ERROR: Data Error in encrypted file. Wrong password? : a.txt\n"
+        ));
+        assert!(is_wrong_password(
+            &Command::new("my-unzip"),
+            exit_status(82),
+            b"   skipping: a.txt                incorrect password\n"
+        ));
+    }
+
+    #[test]
+    fn stderr_tail() {
+        let stderr =
+            (1..=STDERR_TAIL_LINES + 2).map(|i| format!("line {i}\n\n")).collect::<String>();
+
+        let tail = get_stderr_tail(stderr.as_bytes());
+
+        assert_eq!(STDERR_TAIL_LINES, tail.lines().count());
+        assert!(tail.ends_with(format!("line {}", STDERR_TAIL_LINES + 2).as_str()));
+        assert_eq!("", get_stderr_tail(b""));
+    }
+}
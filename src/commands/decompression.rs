@@ -1,14 +1,42 @@
-use std::{fs, fs::File, io, process};
+use std::{
+    fs::File,
+    io,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
 
-use anyhow::{anyhow, Context};
-use execute::{command_args, Execute};
-use path_absolutize::{Absolutize, CWD};
+use path_absolutize::CWD;
+use xcompress::{OverwritePolicy, XcompressError};
 
-use super::read_password;
-use crate::{
-    archive_format::ArchiveFormat,
-    cli::{CLIArgs, CLICommands},
-};
+use super::{ask_password, open_extractor, parse_size, print_warnings};
+use crate::cli::{CLIArgs, CLICommands};
+
+/// Ask the user on the terminal whether to overwrite an existing file. It is skipped unless the answer is yes. If there is no terminal, the answer is read from stdin, unless stdin is the archive.
+fn ask_overwrite(file_path: &Path, stdin_is_input: bool) -> bool {
+    let mut answer = String::new();
+
+    let result = match File::options().read(true).write(true).open("/dev/tty") {
+        Ok(mut tty) => {
+            if write!(tty, "{file_path:?} already exists. Overwrite it? [y/N] ").is_err() {
+                return false;
+            }
+
+            BufReader::new(tty).read_line(&mut answer)
+        },
+        Err(_) if !stdin_is_input => {
+            eprint!("{file_path:?} already exists. Overwrite it? [y/N] ");
+
+            io::stdin().read_line(&mut answer)
+        },
+        Err(_) => return false,
+    };
+
+    if result.is_err() {
+        return false;
+    }
+
+    matches!(answer.trim(), "y" | "Y" | "yes" | "Yes")
+}
 
 pub fn handle_decompression(cli_args: CLIArgs) -> anyhow::Result<()> {
     debug_assert!(matches!(cli_args.command, CLICommands::X { .. }));
@@ -17,16 +45,20 @@ pub fn handle_decompression(cli_args: CLIArgs) -> anyhow::Result<()> {
         input_path,
         output_path,
         output,
+        to_stdout,
+        include,
+        exclude,
+        strip_components,
+        smart,
+        overwrite,
+        unsafe_paths,
+        max_output_size,
+        max_ratio,
+        max_entries,
+        skip_space_check,
+        members,
     } = cli_args.command
     {
-        let input_path = match input_path.canonicalize() {
-            Ok(input_path) => input_path,
-            Err(error) => {
-                return Err(error)
-                    .with_context(|| anyhow!("{:?}", input_path.absolutize().unwrap()));
-            },
-        };
-
         let output_path = if let Some(output_path) = output_path.as_deref() {
             output_path
         } else if let Some(output_path) = output.as_deref() {
@@ -35,943 +67,80 @@ pub fn handle_decompression(cli_args: CLIArgs) -> anyhow::Result<()> {
             CWD.as_path()
         };
 
-        let cpus = if cli_args.single_thread { 1 } else { num_cpus::get() };
-
-        let format = ArchiveFormat::get_archive_format_from_file_path(input_path.as_path())?;
-
-        if cli_args.password.is_some()
-            && !matches!(
-                format,
-                ArchiveFormat::Tar7z | ArchiveFormat::P7z | ArchiveFormat::Zip | ArchiveFormat::Rar
-            )
-        {
-            return Err(anyhow!("`password` only supports 7Z, ZIP and RAR."));
+        let extractor = open_extractor(
+            input_path.as_path(),
+            cli_args.format,
+            Some(&cli_args.password_options),
+            &cli_args.resource_options,
+            cli_args.quiet,
+            cli_args.threads,
+            &cli_args.executable_paths,
+        )?;
+
+        let mut extractor = extractor
+            .overwrite(overwrite)
+            .safe(!unsafe_paths)
+            .check_space(!skip_space_check)
+            .smart(smart)
+            .members(members)
+            .include(include)
+            .exclude(exclude);
+
+        if overwrite == OverwritePolicy::Ask {
+            let stdin_is_input = input_path == Path::new("-");
+
+            extractor =
+                extractor.ask_overwrite(move |file_path| ask_overwrite(file_path, stdin_is_input));
         }
 
-        let output_path = match output_path.canonicalize() {
-            Ok(output_path) => {
-                if !output_path.is_dir() {
-                    return Err(anyhow!("{output_path:?} is not a directory."));
-                }
-
-                output_path
-            },
-            Err(error) if error.kind() == io::ErrorKind::NotFound => {
-                fs::create_dir_all(output_path)
-                    .with_context(|| anyhow!("{:?}", output_path.absolutize().unwrap()))?;
-
-                output_path
-                    .canonicalize()
-                    .with_context(|| anyhow!("{:?}", output_path.absolutize().unwrap()))?
-            },
-            Err(error) => {
-                return Err(error)
-                    .with_context(|| anyhow!("{:?}", output_path.absolutize().unwrap()));
-            },
-        };
-
-        let threads = cpus.to_string();
-        let threads = threads.as_str();
-
-        match format {
-            ArchiveFormat::TarZ | ArchiveFormat::TarGzip => {
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pigz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command1 = command_args!(
-                        &cli_args.executable_paths.pigz_path,
-                        "-d",
-                        "-c",
-                        "-p",
-                        threads,
-                        input_path
-                    );
-                    let mut command2 = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        "-"
-                    );
-
-                    if !cli_args.quiet {
-                        command2.arg("-v");
-                    }
-
-                    let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-z",
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::TarBzip2 => {
-                if cpus > 1 {
-                    if command_args!(&cli_args.executable_paths.lbzip2_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                    {
-                        let mut command1 = command_args!(
-                            &cli_args.executable_paths.lbzip2_path,
-                            "-d",
-                            "-c",
-                            "-n",
-                            threads,
-                            input_path
-                        );
-                        let mut command2 = command_args!(
-                            &cli_args.executable_paths.tar_path,
-                            "-x",
-                            "-C",
-                            output_path.as_path(),
-                            "-f",
-                            "-"
-                        );
-
-                        if !cli_args.quiet {
-                            command2.arg("-v");
-                        }
-
-                        let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                        process::exit(output.status.code().unwrap_or(1));
-                    }
-
-                    if command_args!(&cli_args.executable_paths.pbzip2_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                    {
-                        let mut command1 = command_args!(
-                            &cli_args.executable_paths.pbzip2_path,
-                            "-d",
-                            "-c",
-                            format!("-p{threads}"),
-                            input_path
-                        );
-                        let mut command2 = command_args!(
-                            &cli_args.executable_paths.tar_path,
-                            "-x",
-                            "-C",
-                            output_path.as_path(),
-                            "-f",
-                            "-"
-                        );
-
-                        if !cli_args.quiet {
-                            command2.arg("-v");
-                        }
-
-                        let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                        process::exit(output.status.code().unwrap_or(1));
-                    }
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-j",
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::TarLz => {
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.plzip_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command1 = command_args!(
-                        &cli_args.executable_paths.plzip_path,
-                        "-d",
-                        "-c",
-                        "-n",
-                        threads,
-                        input_path
-                    );
-                    let mut command2 = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        "-"
-                    );
-
-                    if !cli_args.quiet {
-                        command2.arg("-v");
-                    }
-
-                    let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.lunzip_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-I",
-                        &cli_args.executable_paths.lunzip_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        input_path
-                    );
-
-                    if !cli_args.quiet {
-                        command.arg("-v");
-                    }
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-I",
-                    &cli_args.executable_paths.lzip_path,
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::TarXz => {
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pxz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command1 = command_args!(
-                        &cli_args.executable_paths.pxz_path,
-                        "-d",
-                        "-c",
-                        "-T",
-                        threads,
-                        input_path
-                    );
-                    let mut command2 = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        "-"
-                    );
-
-                    if !cli_args.quiet {
-                        command2.arg("-v");
-                    }
-
-                    let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-J",
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::TarLzma => {
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pxz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command1 = command_args!(
-                        &cli_args.executable_paths.pxz_path,
-                        "-d",
-                        "-c",
-                        "-T",
-                        threads,
-                        "-F",
-                        "lzma",
-                        input_path
-                    );
-                    let mut command2 = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        "-"
-                    );
-
-                    if !cli_args.quiet {
-                        command2.arg("-v");
-                    }
-
-                    let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.unlzma_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-I",
-                        &cli_args.executable_paths.unlzma_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        input_path
-                    );
-
-                    if !cli_args.quiet {
-                        command.arg("-v");
-                    }
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-I",
-                    &cli_args.executable_paths.lzma_path,
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Tar7z => {
-                let password = read_password(cli_args.password)?;
-
-                let mut command1 = command_args!(
-                    &cli_args.executable_paths.p7z_path,
-                    "x",
-                    "-so",
-                    format!("-mmt{threads}")
-                );
-
-                command1.arg(format!("-p{password}"));
-
-                command1.arg(input_path);
-
-                let mut command2 = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    "-"
-                );
-
-                if !cli_args.quiet {
-                    command2.arg("-v");
-                }
-
-                let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::TarZstd => {
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pzstd_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command1 = command_args!(
-                        &cli_args.executable_paths.pzstd_path,
-                        "-d",
-                        "-c",
-                        "-p",
-                        threads,
-                        input_path
-                    );
-                    let mut command2 = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        "-"
-                    );
-
-                    if !cli_args.quiet {
-                        command2.arg("-v");
-                    }
-
-                    let output = command1.execute_multiple_output(&mut [&mut command2])?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.unzstd_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.tar_path,
-                        "-I",
-                        &cli_args.executable_paths.unzstd_path,
-                        "-x",
-                        "-C",
-                        output_path.as_path(),
-                        "-f",
-                        input_path
-                    );
-
-                    if !cli_args.quiet {
-                        command.arg("-v");
-                    }
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-I",
-                    &cli_args.executable_paths.zstd_path,
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Tar => {
-                let mut command = command_args!(
-                    &cli_args.executable_paths.tar_path,
-                    "-x",
-                    "-C",
-                    output_path.as_path(),
-                    "-f",
-                    input_path
-                );
-
-                if !cli_args.quiet {
-                    command.arg("-v");
-                }
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Z | ArchiveFormat::Gzip => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pigz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.pigz_path,
-                        "-d",
-                        "-c",
-                        "-p",
-                        threads,
-                        input_path
-                    );
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.gnuzip_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.gnuzip_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.gzip_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Bzip2 => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1 {
-                    if command_args!(&cli_args.executable_paths.lbzip2_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                    {
-                        let mut command = command_args!(
-                            &cli_args.executable_paths.lbzip2_path,
-                            "-d",
-                            "-c",
-                            "-n",
-                            threads,
-                            input_path
-                        );
-
-                        command.stdout(file);
-
-                        let output = command.execute_output()?;
-
-                        process::exit(output.status.code().unwrap_or(1));
-                    }
-
-                    if command_args!(&cli_args.executable_paths.pbzip2_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                    {
-                        let mut command = command_args!(
-                            &cli_args.executable_paths.pbzip2_path,
-                            "-d",
-                            "-c",
-                            format!("-p{threads}"),
-                            input_path
-                        );
-
-                        command.stdout(file);
-
-                        let output = command.execute_output()?;
-
-                        process::exit(output.status.code().unwrap_or(1));
-                    }
-                }
-
-                if command_args!(&cli_args.executable_paths.bunzip2_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.bunzip2_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.bzip2_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Lz => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.plzip_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.plzip_path,
-                        "-d",
-                        "-c",
-                        "-n",
-                        threads,
-                        input_path
-                    );
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.lunzip_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.lunzip_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.lzip_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Xz => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pxz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.pxz_path,
-                        "-d",
-                        "-c",
-                        "-T",
-                        threads,
-                        input_path
-                    );
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.unxz_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.unxz_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.xz_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Lzma => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pxz_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.pxz_path,
-                        "-d",
-                        "-c",
-                        "-T",
-                        threads,
-                        "-F",
-                        "lzma",
-                        input_path
-                    );
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.unlzma_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.unlzma_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.lzma_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::P7z => {
-                let password = read_password(cli_args.password)?;
-
-                let mut command = command_args!(
-                    &cli_args.executable_paths.p7z_path,
-                    "x",
-                    "-aoa",
-                    format!("-mmt{threads}"),
-                    format!("-o{}", output_path.to_string_lossy())
-                );
-
-                command.arg(format!("-p{password}"));
-
-                command.arg(input_path);
-
-                if cli_args.quiet {
-                    process::exit(command.execute()?.unwrap_or(1));
-                } else {
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-            },
-            ArchiveFormat::Zip => {
-                let password = read_password(cli_args.password)?;
-
-                let mut command = command_args!(&cli_args.executable_paths.unzip_path);
-
-                command.arg("-P");
-                command.arg(password);
-
-                if cli_args.quiet {
-                    command.arg("-qq");
-                }
-
-                command.args(["-O", "UTF-8", "-o"]);
-                command.arg(input_path);
-                command.arg("-d");
-                command.arg(output_path.as_path());
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
-            },
-            ArchiveFormat::Rar => {
-                let password = read_password(cli_args.password)?;
-
-                if command_args!(&cli_args.executable_paths.unrar_path, "-?")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.unrar_path, "x", "-o+");
-
-                    command.arg(format!("-mt{threads}"));
+        if let Some(strip_components) = strip_components {
+            extractor = extractor.strip_components(strip_components);
+        }
 
-                    if password.is_empty() {
-                        command.arg("-p-");
-                    } else {
-                        command.arg(format!("-p{password}"));
-                    }
+        if let Some(max_output_size) = max_output_size {
+            extractor = extractor.max_output_size(parse_size("max-output-size", &max_output_size)?);
+        }
 
-                    if cli_args.quiet {
-                        command.arg("-idq");
-                    }
+        if let Some(max_ratio) = max_ratio {
+            extractor = extractor.max_ratio(max_ratio);
+        }
 
-                    command.arg(input_path);
-                    command.arg(output_path.as_path());
+        if let Some(max_entries) = max_entries {
+            extractor = extractor.max_entries(max_entries);
+        }
 
-                    let output = command.execute_output()?;
+        if to_stdout {
+            extractor.decompress_to_stdout()?;
 
-                    process::exit(output.status.code().unwrap_or(1));
-                }
+            return Ok(());
+        }
 
-                let mut command = command_args!(&cli_args.executable_paths.rar_path, "x", "-o+");
+        // the password is asked at most once, if it is not given but needed
+        let mut asked = false;
 
-                command.arg(format!("-mt{threads}"));
+        if !extractor.has_password() && extractor.is_encrypted()? {
+            if let Some(password) = ask_password()? {
+                extractor = extractor.password(password);
 
-                if password.is_empty() {
-                    command.arg("-p-");
-                } else {
-                    command.arg(format!("-p{password}"));
-                }
+                asked = true;
+            }
+        }
 
-                if cli_args.quiet {
-                    command.arg("-idq");
+        let report = match extractor.extract_to(output_path) {
+            Err(XcompressError::WrongPassword)
+                if !asked
+                    && !extractor.has_password()
+                    && extractor.archive_format()?.supports_password() =>
+            {
+                match ask_password()? {
+                    Some(password) => extractor.password(password).extract_to(output_path)?,
+                    None => return Err(XcompressError::WrongPassword.into()),
                 }
-
-                command.arg(input_path);
-                command.arg(output_path.as_path());
-
-                let output = command.execute_output()?;
-
-                process::exit(output.status.code().unwrap_or(1));
             },
-            ArchiveFormat::Zstd => {
-                let file_path = output_path.join(input_path.file_stem().unwrap());
-
-                if file_path.is_dir() {
-                    return Err(anyhow!("{file_path:?} is a directory."));
-                }
-
-                let file =
-                    File::create(file_path.as_path()).with_context(|| anyhow!("{file_path:?}"))?;
-
-                if cpus > 1
-                    && command_args!(&cli_args.executable_paths.pzstd_path, "-V")
-                        .execute_check_exit_status_code(0)
-                        .is_ok()
-                {
-                    let mut command = command_args!(
-                        &cli_args.executable_paths.pzstd_path,
-                        "-d",
-                        "-c",
-                        "-p",
-                        threads,
-                        input_path
-                    );
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                if command_args!(&cli_args.executable_paths.unzstd_path, "-V")
-                    .execute_check_exit_status_code(0)
-                    .is_ok()
-                {
-                    let mut command =
-                        command_args!(&cli_args.executable_paths.unzstd_path, "-c", input_path);
-
-                    command.stdout(file);
-
-                    let output = command.execute_output()?;
-
-                    process::exit(output.status.code().unwrap_or(1));
-                }
-
-                let mut command =
-                    command_args!(&cli_args.executable_paths.zstd_path, "-d", "-c", input_path);
-
-                command.stdout(file);
-
-                let output = command.execute_output()?;
+            result => result?,
+        };
 
-                process::exit(output.status.code().unwrap_or(1));
-            },
-        }
+        print_warnings(&report, cli_args.quiet);
     }
 
     Ok(())
@@ -0,0 +1,127 @@
+use std::{io, io::Write, path::Path};
+
+use serde::Serialize;
+use xcompress::{ArchiveEntry, EntryType};
+
+use super::open_extractor;
+use crate::cli::{CLIArgs, CLICommands};
+
+#[derive(Debug, Serialize)]
+struct Listing<'a> {
+    path:    &'a Path,
+    format:  String,
+    entries: &'a [ArchiveEntry],
+}
+
+pub fn handle_list(cli_args: CLIArgs) -> anyhow::Result<()> {
+    debug_assert!(matches!(cli_args.command, CLICommands::L { .. }));
+
+    if let CLICommands::L {
+        input_path,
+        json,
+    } = cli_args.command
+    {
+        let extractor = open_extractor(
+            input_path.as_path(),
+            cli_args.format,
+            Some(&cli_args.password_options),
+            &cli_args.resource_options,
+            cli_args.quiet,
+            cli_args.threads,
+            &cli_args.executable_paths,
+        )?;
+
+        let entries = extractor.list()?;
+
+        let mut stdout = io::stdout().lock();
+
+        let result = if json {
+            let listing = Listing {
+                path:    extractor.input_path(),
+                format:  extractor.archive_format()?.to_string(),
+                entries: &entries,
+            };
+
+            serde_json::to_writer_pretty(&mut stdout, &listing)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(stdout))
+        } else {
+            print_table(&mut stdout, &entries)
+        };
+
+        match result {
+            Ok(()) => (),
+            // e.g. piped to `head`
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => (),
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    Ok(())
+}
+
+fn print_table<W: Write>(mut writer: W, entries: &[ArchiveEntry]) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{:<10}  {:>12}  {:>12}  {:<19}  Name",
+        "Mode", "Size", "Compressed", "Modified"
+    )?;
+
+    let mut files = 0;
+    let mut directories = 0;
+    let mut total_size = 0;
+    let mut has_encrypted = false;
+
+    for entry in entries {
+        let mode = match entry.mode.as_deref() {
+            Some(mode) => mode,
+            None => match entry.entry_type {
+                EntryType::File => "-",
+                EntryType::Directory => "d",
+                EntryType::Symlink => "l",
+                EntryType::Hardlink => "h",
+                EntryType::Other => "?",
+            },
+        };
+
+        let size = entry.size.map(|size| size.to_string()).unwrap_or_else(|| "-".into());
+        let compressed_size =
+            entry.compressed_size.map(|size| size.to_string()).unwrap_or_else(|| "-".into());
+        let mtime = entry.mtime.as_deref().unwrap_or("-");
+
+        write!(writer, "{mode:<10}  {size:>12}  {compressed_size:>12}  {mtime:<19}  ")?;
+
+        if entry.encrypted {
+            has_encrypted = true;
+
+            write!(writer, "*")?;
+        }
+
+        write!(writer, "{}", entry.path)?;
+
+        if let Some(link_target) = entry.link_target.as_deref() {
+            match entry.entry_type {
+                EntryType::Hardlink => write!(writer, " link to {link_target}")?,
+                _ => write!(writer, " -> {link_target}")?,
+            }
+        }
+
+        writeln!(writer)?;
+
+        match entry.entry_type {
+            EntryType::Directory => directories += 1,
+            _ => {
+                files += 1;
+                total_size += entry.size.unwrap_or(0);
+            },
+        }
+    }
+
+    writeln!(writer, "{files} file(s), {directories} directory(ies), {total_size} byte(s)")?;
+
+    if has_encrypted {
+        writeln!(writer, "* encrypted")?;
+    }
+
+    Ok(())
+}
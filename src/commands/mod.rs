@@ -1,32 +1,83 @@
 mod compression;
 mod decompression;
+mod list;
+mod password;
+mod test;
 
-use std::{fs, io, io::Write, path::Path};
+use std::path::Path;
 
-use anyhow::anyhow;
+use byte_unit::Byte;
 pub use compression::*;
 pub use decompression::*;
-use execute::generic_array::typenum::U32;
-use scanner_rust::Scanner;
+pub use list::*;
+use password::*;
+use path_absolutize::Absolutize;
+pub use test::*;
+use xcompress::{ArchiveFormat, ExecutablePaths, Extractor, Report, XcompressError};
 
-#[inline]
-fn try_delete_file<P: AsRef<Path>>(file_path: P) {
-    if fs::remove_file(file_path).is_err() {}
+use crate::cli::{PasswordOptions, ResourceOptions};
+
+fn open_extractor(
+    input_path: &Path,
+    format: Option<ArchiveFormat>,
+    password_options: Option<&PasswordOptions>,
+    resource_options: &ResourceOptions,
+    quiet: bool,
+    threads: usize,
+    executable_paths: &ExecutablePaths,
+) -> anyhow::Result<Extractor> {
+    // `-` is stdin, which must not be resolved against the working directory
+    let input_path =
+        if input_path == Path::new("-") { input_path.into() } else { input_path.absolutize()? };
+
+    let mut extractor = Extractor::open(input_path)?
+        .threads(threads)
+        .quiet(quiet)
+        .executable_paths(executable_paths.clone());
+
+    if let Some(memory_limit) = resource_options.memory_limit.as_deref() {
+        extractor = extractor.memory_limit(parse_size("memory-limit", memory_limit)?);
+    }
+
+    if let Some(nice) = resource_options.nice {
+        extractor = extractor.nice(nice);
+    }
+
+    if let Some(ionice) = resource_options.ionice {
+        extractor = extractor.ionice(ionice);
+    }
+
+    let format = match format {
+        Some(format) => format,
+        None => extractor.archive_format()?,
+    };
+
+    extractor = extractor.format(format);
+
+    if let Some(password_options) = password_options {
+        if let Some(password) = get_password(password_options, format, false)? {
+            extractor = extractor.password(password);
+        }
+    }
+
+    Ok(extractor)
 }
-fn read_password(password: Option<String>) -> anyhow::Result<String> {
-    match password {
-        Some(password) => {
-            if password.is_empty() {
-                print!("Password (visible): ");
-                io::stdout().flush()?;
-
-                let mut sc: Scanner<_, U32> = Scanner::new2(io::stdin());
-
-                sc.next_line()?.ok_or_else(|| anyhow!("Stdin is closed."))
-            } else {
-                Ok(password)
-            }
-        },
-        None => Ok(String::new()),
+
+/// The library does not print anything, so its warnings are printed here.
+fn print_warnings(report: &Report, quiet: bool) {
+    if !quiet {
+        for warning in report.warnings.iter() {
+            eprintln!("{warning}");
+        }
+    }
+}
+
+/// Parse a size like `10MiB` of the option. An invalid size is an invalid option rather than a general error.
+fn parse_size(option: &str, size: &str) -> Result<u64, XcompressError> {
+    match Byte::parse_str(size, true) {
+        Ok(byte) => Ok(byte.as_u64()),
+        Err(error) => Err(XcompressError::InvalidOption(format!(
+            "Invalid size {size:?} for --{option}: {error}"
+        ))),
     }
 }
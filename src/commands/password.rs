@@ -0,0 +1,158 @@
+use std::{
+    env,
+    fs::File,
+    io,
+    io::{BufRead, BufReader, Read, Write},
+    mem::{ManuallyDrop, MaybeUninit},
+    os::fd::{AsRawFd, FromRawFd},
+};
+
+use anyhow::anyhow;
+use xcompress::{ArchiveFormat, XcompressError};
+
+use crate::cli::PasswordOptions;
+
+const PASSWORD_ENV: &str = "XCOMPRESS_PASSWORD";
+
+/// Whether a password is set by the options. The environment variable is not counted.
+#[inline]
+pub(super) fn has_password_option(options: &PasswordOptions) -> bool {
+    options.password.is_some() || options.password_file.is_some() || options.password_fd.is_some()
+}
+
+/// Read the password from the options, or from the environment variable. The password entered on the terminal is asked twice if `confirm` is true.
+pub(super) fn read_password(
+    options: &PasswordOptions,
+    confirm: bool,
+) -> anyhow::Result<Option<String>> {
+    if let Some(password) = options.password.as_deref() {
+        if !password.is_empty() {
+            return Ok(Some(password.to_string()));
+        }
+
+        let password = prompt_password("Password: ")?;
+
+        if confirm && prompt_password("Confirm password: ")? != password {
+            return Err(
+                XcompressError::InvalidOption(String::from("The passwords do not match.")).into()
+            );
+        }
+
+        return Ok(Some(password));
+    }
+
+    if let Some(password_file) = options.password_file.as_deref() {
+        let file = File::open(password_file).map_err(|error| XcompressError::Io {
+            path:   Some(password_file.to_path_buf()),
+            source: error,
+        })?;
+
+        return read_first_line(file).map(Some);
+    }
+
+    if let Some(fd) = options.password_fd {
+        // stdin may be the archive, and the password must not be read from an output
+        if (0..=2).contains(&fd) {
+            return Err(XcompressError::InvalidOption(format!(
+                "The file descriptor {fd} is stdin, stdout or stderr. Use `--password-file \
+                 /dev/stdin` to read the password from stdin."
+            ))
+            .into());
+        }
+
+        if unsafe { libc::fcntl(fd, libc::F_GETFD) } < 0 {
+            return Err(XcompressError::InvalidOption(format!(
+                "The file descriptor {fd} is not open."
+            ))
+            .into());
+        }
+
+        // the file descriptor is inherited from the parent, which owns it, so it is not closed
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+
+        return read_first_line(&mut *file).map(Some);
+    }
+
+    Ok(env::var(PASSWORD_ENV).ok().filter(|password| !password.is_empty()))
+}
+
+/// Only read the password if the format supports it. Otherwise, an empty password is returned if any option is set so that it can be rejected, and the environment variable is ignored.
+pub(super) fn get_password(
+    options: &PasswordOptions,
+    format: ArchiveFormat,
+    confirm: bool,
+) -> anyhow::Result<Option<String>> {
+    if format.supports_password() {
+        read_password(options, confirm)
+    } else if has_password_option(options) {
+        Ok(Some(String::new()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Ask for a password on the terminal without echo. `None` is returned if there is no terminal.
+pub(super) fn ask_password() -> anyhow::Result<Option<String>> {
+    if File::options().read(true).write(true).open("/dev/tty").is_err() {
+        return Ok(None);
+    }
+
+    prompt_password("Password: ").map(Some)
+}
+
+fn read_first_line<R: Read>(reader: R) -> anyhow::Result<String> {
+    let mut line = String::new();
+
+    BufReader::new(reader).read_line(&mut line)?;
+
+    let password = line.trim_end_matches(['\r', '\n']);
+
+    if password.is_empty() {
+        return Err(anyhow!("The password is empty."));
+    }
+
+    Ok(password.to_string())
+}
+
+/// Read a password on the terminal without echo. If there is no terminal, it is read from stdin.
+fn prompt_password(prompt: &str) -> anyhow::Result<String> {
+    let mut tty = match File::options().read(true).write(true).open("/dev/tty") {
+        Ok(tty) => tty,
+        Err(_) => return read_first_line(io::stdin().lock()),
+    };
+
+    let fd = tty.as_raw_fd();
+
+    let mut termios = MaybeUninit::<libc::termios>::uninit();
+
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    let original = unsafe { termios.assume_init() };
+
+    let mut hidden = original;
+    hidden.c_lflag &= !libc::ECHO;
+    hidden.c_lflag |= libc::ECHONL;
+
+    tty.write_all(prompt.as_bytes())?;
+    tty.flush()?;
+
+    if unsafe { libc::tcsetattr(fd, libc::TCSAFLUSH, &hidden) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    let mut line = String::new();
+
+    let result = BufReader::new(&mut tty).read_line(&mut line);
+
+    unsafe {
+        libc::tcsetattr(fd, libc::TCSAFLUSH, &original);
+    }
+
+    if result? == 0 {
+        return Err(anyhow!("The terminal is closed."));
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -0,0 +1,107 @@
+use xcompress::{ArchiveFormat, Extractor, XcompressError};
+
+use super::{has_password_option, open_extractor, read_password};
+use crate::cli::{CLIArgs, CLICommands};
+
+/// A corrupted archive is the most important thing to report.
+#[inline]
+fn get_severity(result: &anyhow::Result<()>) -> u8 {
+    match result {
+        Ok(()) => 0,
+        Err(error) => match error.downcast_ref::<XcompressError>() {
+            Some(XcompressError::ToolNotFound(_)) => 1,
+            Some(XcompressError::WrongPassword) => 2,
+            Some(XcompressError::Corrupted) => 4,
+            _ => 3,
+        },
+    }
+}
+
+pub fn handle_test(cli_args: CLIArgs) -> anyhow::Result<()> {
+    debug_assert!(matches!(cli_args.command, CLICommands::T { .. }));
+
+    if let CLICommands::T {
+        input_paths,
+    } = cli_args.command
+    {
+        // an archive file which cannot be opened is reported with the others
+        let extractors: Vec<anyhow::Result<Extractor>> = input_paths
+            .iter()
+            .map(|input_path| {
+                // the password is asked later, only once
+                open_extractor(
+                    input_path.as_path(),
+                    cli_args.format,
+                    None,
+                    &cli_args.resource_options,
+                    cli_args.quiet,
+                    cli_args.threads,
+                    &cli_args.executable_paths,
+                )
+            })
+            .collect();
+
+        let formats: Vec<ArchiveFormat> = extractors
+            .iter()
+            .filter_map(|extractor| extractor.as_ref().ok()?.archive_format().ok())
+            .collect();
+
+        // the password is only applied to the archive files which support it
+        let password = if has_password_option(&cli_args.password_options) {
+            if !formats.is_empty() && !formats.iter().any(|format| format.supports_password()) {
+                return Err(XcompressError::UnsupportedOption {
+                    option:    "password",
+                    format:    formats[0],
+                    supported: "7Z, ZIP and RAR",
+                }
+                .into());
+            }
+
+            read_password(&cli_args.password_options, false)?
+        } else if formats.iter().any(|format| format.supports_password()) {
+            read_password(&cli_args.password_options, false)?
+        } else {
+            None
+        };
+
+        let mut results: Vec<anyhow::Result<()>> = extractors
+            .into_iter()
+            .map(|extractor| {
+                let extractor = extractor?;
+
+                let extractor = match password.as_deref() {
+                    Some(password) if extractor.archive_format()?.supports_password() => {
+                        extractor.password(password)
+                    },
+                    _ => extractor,
+                };
+
+                extractor.test().map_err(Into::into)
+            })
+            .collect();
+
+        if input_paths.len() > 1 && !cli_args.quiet {
+            for (input_path, result) in input_paths.iter().zip(results.iter()) {
+                let status = match result {
+                    Ok(()) => String::from("OK"),
+                    Err(error) => match error.downcast_ref::<XcompressError>() {
+                        Some(XcompressError::WrongPassword) => String::from("WRONG PASSWORD"),
+                        Some(XcompressError::ToolNotFound(program)) => {
+                            format!("TOOL NOT FOUND ({program})")
+                        },
+                        Some(XcompressError::Corrupted) => String::from("CORRUPTED"),
+                        _ => String::from("ERROR"),
+                    },
+                };
+
+                println!("{status:<14} {}", input_path.to_string_lossy());
+            }
+        }
+
+        if let Some(index) = (0..results.len()).max_by_key(|&i| get_severity(&results[i])) {
+            results.swap_remove(index)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::{
+    ffi::CString,
+    fs, io,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use byte_unit::{Byte, UnitType};
+
+use crate::{error::IoResultExt, XcompressError};
+
+/// Count the entries in a directory recursively, and sum the sizes of the files. Symbolic links are not followed.
+pub(crate) fn get_directory_usage(dir: &Path) -> io::Result<(u64, u64)> {
+    let mut entries = 0;
+    let mut size = 0;
+
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let read_dir = match fs::read_dir(dir.as_path()) {
+            Ok(read_dir) => read_dir,
+            // the directory may be replaced while being extracted
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+
+        for entry in read_dir {
+            let entry = entry?;
+
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error),
+            };
+
+            entries += 1;
+
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                size += metadata.len();
+            }
+        }
+    }
+
+    Ok((entries, size))
+}
+
+/// Sum the sizes of the files and directories.
+pub(crate) fn get_total_size(paths: &[PathBuf]) -> Result<u64, XcompressError> {
+    let mut size = 0;
+
+    for path in paths {
+        let metadata = fs::symlink_metadata(path).with_path(path)?;
+
+        if metadata.is_dir() {
+            size += get_directory_usage(path).with_path(path)?.1;
+        } else {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Get the free space for unprivileged users on the filesystem where the path is, or would be created.
+pub(crate) fn get_available_space(path: &Path) -> io::Result<u64> {
+    // the nearest existing ancestor is on the same filesystem
+    let path = path.ancestors().find(|path| path.exists()).unwrap_or(Path::new("/"));
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let stat = unsafe { stat.assume_init() };
+
+    // the types of the fields are different on different platforms
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Return an error if the filesystem where the path is does not have the required space.
+pub(crate) fn check_available_space(path: &Path, required: u64) -> Result<(), XcompressError> {
+    let available = match get_available_space(path) {
+        Ok(available) => available,
+        // the check is not possible, e.g. on some network filesystems
+        Err(_) => return Ok(()),
+    };
+
+    if required > available {
+        return Err(XcompressError::InsufficientSpace {
+            path: path.to_path_buf(),
+            required,
+            available,
+        });
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn format_size(size: u64) -> String {
+    format!("{:.2}", Byte::from_u64(size).get_appropriate_unit(UnitType::Binary))
+}
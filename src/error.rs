@@ -0,0 +1,183 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{disk_space::format_size, ArchiveFormat};
+
+#[derive(Debug)]
+pub enum XcompressError {
+    /// An I/O error, on the path if it is known.
+    Io {
+        path:   Option<PathBuf>,
+        source: io::Error,
+    },
+    /// The archive format cannot be determined.
+    UnknownFormat,
+    /// The path cannot be used, e.g. a directory is given where a file is expected.
+    InvalidPath {
+        path:   PathBuf,
+        reason: &'static str,
+    },
+    /// A volume of the split archive cannot be found.
+    MissingVolume(PathBuf),
+    /// The options cannot be applied to the input files, or have invalid values.
+    InvalidOption(String),
+    /// The option is valid, but the archive format does not support it.
+    UnsupportedOption {
+        option:    &'static str,
+        format:    ArchiveFormat,
+        supported: &'static str,
+    },
+    ToolNotFound(String),
+    /// An external program exited unsuccessfully. `code` is `None` if it was killed by a signal.
+    ToolFailed {
+        program:     String,
+        code:        Option<i32>,
+        signal:      Option<i32>,
+        /// The last lines which the program printed to stderr.
+        stderr_tail: String,
+    },
+    WrongPassword,
+    Corrupted,
+    /// The archive file contains entries which can be extracted outside the destination directory. The paths of them are paired with the reasons.
+    UnsafeEntries(Vec<(String, &'static str)>),
+    /// The extraction is aborted because of the limit, like the output size.
+    LimitExceeded(&'static str),
+    /// The filesystem does not have enough free space for the output.
+    InsufficientSpace {
+        path:      PathBuf,
+        required:  u64,
+        available: u64,
+    },
+}
+
+impl From<io::Error> for XcompressError {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        XcompressError::Io {
+            path: None, source: error
+        }
+    }
+}
+
+impl Display for XcompressError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            XcompressError::Io {
+                path,
+                source,
+            } => match path {
+                Some(path) => write!(f, "{path:?}: {source}"),
+                None => Display::fmt(source, f),
+            },
+            XcompressError::UnknownFormat => f.write_str("Unknown archive format."),
+            XcompressError::InvalidPath {
+                path,
+                reason,
+            } => write!(f, "{path:?} {reason}."),
+            XcompressError::MissingVolume(path) => write!(f, "The volume {path:?} is missing."),
+            XcompressError::InvalidOption(message) => f.write_str(message),
+            XcompressError::UnsupportedOption {
+                option,
+                format,
+                supported,
+            } => {
+                write!(f, "`{option}` is not supported by {format}. It only supports {supported}.")
+            },
+            XcompressError::ToolNotFound(program) => write!(f, "{program:?} is not found."),
+            XcompressError::ToolFailed {
+                program,
+                code,
+                signal,
+                stderr_tail,
+            } => {
+                match (code, signal) {
+                    (Some(code), _) => write!(f, "{program:?} failed with exit code {code}.")?,
+                    (None, Some(signal)) => match get_signal_name(*signal) {
+                        Some(name) => {
+                            write!(f, "{program:?} was killed by signal {signal} ({name}).")?
+                        },
+                        None => write!(f, "{program:?} was killed by signal {signal}.")?,
+                    },
+                    (None, None) => write!(f, "{program:?} was terminated.")?,
+                }
+
+                if !stderr_tail.is_empty() {
+                    write!(f, "\n{stderr_tail}")?;
+                }
+
+                Ok(())
+            },
+            XcompressError::WrongPassword => f.write_str("The password is incorrect."),
+            XcompressError::Corrupted => f.write_str("The archive file is corrupted."),
+            XcompressError::UnsafeEntries(entries) => {
+                f.write_str("The archive file contains unsafe entries.")?;
+
+                for (path, reason) in entries {
+                    write!(f, "\n{path:?} {reason}.")?;
+                }
+
+                Ok(())
+            },
+            XcompressError::LimitExceeded(limit) => {
+                write!(f, "The limit of the {limit} is exceeded.")
+            },
+            XcompressError::InsufficientSpace {
+                path,
+                required,
+                available,
+            } => write!(
+                f,
+                "{path:?} does not have enough free space. {} is required, but only {} is \
+                 available.",
+                format_size(*required),
+                format_size(*available)
+            ),
+        }
+    }
+}
+
+impl Error for XcompressError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            // the message of the I/O error is already displayed
+            XcompressError::Io {
+                source, ..
+            } => source.source(),
+            _ => None,
+        }
+    }
+}
+
+/// Attach a path to an I/O error.
+pub(crate) trait IoResultExt<T> {
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T, XcompressError>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    #[inline]
+    fn with_path<P: AsRef<Path>>(self, path: P) -> Result<T, XcompressError> {
+        self.map_err(|source| XcompressError::Io {
+            path: Some(path.as_ref().to_path_buf()),
+            source,
+        })
+    }
+}
+
+fn get_signal_name(signal: i32) -> Option<&'static str> {
+    match signal {
+        1 => Some("SIGHUP"),
+        2 => Some("SIGINT"),
+        3 => Some("SIGQUIT"),
+        6 => Some("SIGABRT"),
+        9 => Some("SIGKILL"),
+        11 => Some("SIGSEGV"),
+        13 => Some("SIGPIPE"),
+        15 => Some("SIGTERM"),
+        _ => None,
+    }
+}
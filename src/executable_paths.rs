@@ -0,0 +1,249 @@
+use clap::Args;
+
+const DEFAULT_COMPRESS_PATH: &str = "compress";
+const DEFAULT_ZIP_PATH: &str = "zip";
+const DEFAULT_UNZIP_PATH: &str = "unzip";
+const DEFAULT_GZIP_PATH: &str = "gzip";
+const DEFAULT_GUNZIP_PATH: &str = "gunzip";
+const DEFAULT_PIGZ_PATH: &str = "pigz";
+const DEFAULT_BZIP2_PATH: &str = "bzip2";
+const DEFAULT_BUNZIP2_PATH: &str = "bunzip2";
+const DEFAULT_LBZIP2_PATH: &str = "lbzip2";
+const DEFAULT_PBZIP2_PATH: &str = "pbzip2";
+const DEFAULT_LZIP_PATH: &str = "lzip";
+const DEFAULT_LUNZIP_PATH: &str = "lunzip";
+const DEFAULT_PLZIP_PATH: &str = "plzip";
+const DEFAULT_XZ_PATH: &str = "xz";
+const DEFAULT_UNXZ_PATH: &str = "unxz";
+const DEFAULT_PXZ_PATH: &str = "pxz";
+const DEFAULT_PIXZ_PATH: &str = "pixz";
+const DEFAULT_LZMA_PATH: &str = "lzma";
+const DEFAULT_UNLZMA_PATH: &str = "unlzma";
+const DEFAULT_7Z_PATH: &str = "7z";
+const DEFAULT_TAR_PATH: &str = "tar";
+const DEFAULT_RAR_PATH: &str = "rar";
+const DEFAULT_UNRAR_PATH: &str = "unrar";
+const DEFAULT_ZSTD_PATH: &str = "zstd";
+const DEFAULT_UNZSTD_PATH: &str = "unzstd";
+const DEFAULT_PZSTD_PATH: &str = "pzstd";
+
+/// The paths of the external programs which are used as backends.
+#[derive(Debug, Clone, Args)]
+pub struct ExecutablePaths {
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_COMPRESS_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your compress executable binary file")]
+    pub compress_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_ZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your zip executable binary file")]
+    pub zip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_UNZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your unzip executable binary file")]
+    pub unzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_GZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your gzip executable binary file")]
+    pub gzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_GUNZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your gunzip executable binary file")]
+    pub gnuzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PIGZ_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your pigz executable binary file")]
+    pub pigz_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_BZIP2_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your bzip2 executable binary file")]
+    pub bzip2_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_BUNZIP2_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your bunzip2 executable binary file")]
+    pub bunzip2_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_LBZIP2_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your lbzip2 executable binary file")]
+    pub lbzip2_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PBZIP2_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your pbzip2 executable binary file")]
+    pub pbzip2_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_LZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your lzip executable binary file")]
+    pub lzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_LUNZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your lunzip executable binary file")]
+    pub lunzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PLZIP_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your plzip executable binary file")]
+    pub plzip_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_XZ_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your xz executable binary file")]
+    pub xz_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_UNXZ_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your unxz executable binary file")]
+    pub unxz_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PXZ_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your pxz executable binary file")]
+    pub pxz_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PIXZ_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your pixz executable binary file")]
+    pub pixz_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_LZMA_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your lzma executable binary file")]
+    pub lzma_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_UNLZMA_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your unlzma executable binary file")]
+    pub unlzma_path: String,
+
+    #[arg(name = "7z-path", value_name = "7Z_PATH")]
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_7Z_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your 7z executable binary file")]
+    pub p7z_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_TAR_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your tar executable binary file")]
+    pub tar_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_RAR_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your rar executable binary file")]
+    pub rar_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_UNRAR_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your unrar executable binary file")]
+    pub unrar_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_ZSTD_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your zstd executable binary file")]
+    pub zstd_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_UNZSTD_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your unzstd executable binary file")]
+    pub unzstd_path: String,
+
+    #[arg(long)]
+    #[arg(global = true)]
+    #[arg(default_value = DEFAULT_PZSTD_PATH)]
+    #[arg(value_hint = clap::ValueHint::CommandName)]
+    #[arg(help = "Specify the path of your pzstd executable binary file")]
+    pub pzstd_path: String,
+}
+
+impl Default for ExecutablePaths {
+    #[inline]
+    fn default() -> Self {
+        ExecutablePaths {
+            compress_path: DEFAULT_COMPRESS_PATH.into(),
+            zip_path:      DEFAULT_ZIP_PATH.into(),
+            unzip_path:    DEFAULT_UNZIP_PATH.into(),
+            gzip_path:     DEFAULT_GZIP_PATH.into(),
+            gnuzip_path:   DEFAULT_GUNZIP_PATH.into(),
+            pigz_path:     DEFAULT_PIGZ_PATH.into(),
+            bzip2_path:    DEFAULT_BZIP2_PATH.into(),
+            bunzip2_path:  DEFAULT_BUNZIP2_PATH.into(),
+            lbzip2_path:   DEFAULT_LBZIP2_PATH.into(),
+            pbzip2_path:   DEFAULT_PBZIP2_PATH.into(),
+            lzip_path:     DEFAULT_LZIP_PATH.into(),
+            lunzip_path:   DEFAULT_LUNZIP_PATH.into(),
+            plzip_path:    DEFAULT_PLZIP_PATH.into(),
+            xz_path:       DEFAULT_XZ_PATH.into(),
+            unxz_path:     DEFAULT_UNXZ_PATH.into(),
+            pxz_path:      DEFAULT_PXZ_PATH.into(),
+            pixz_path:     DEFAULT_PIXZ_PATH.into(),
+            lzma_path:     DEFAULT_LZMA_PATH.into(),
+            unlzma_path:   DEFAULT_UNLZMA_PATH.into(),
+            p7z_path:      DEFAULT_7Z_PATH.into(),
+            tar_path:      DEFAULT_TAR_PATH.into(),
+            rar_path:      DEFAULT_RAR_PATH.into(),
+            unrar_path:    DEFAULT_UNRAR_PATH.into(),
+            zstd_path:     DEFAULT_ZSTD_PATH.into(),
+            unzstd_path:   DEFAULT_UNZSTD_PATH.into(),
+            pzstd_path:    DEFAULT_PZSTD_PATH.into(),
+        }
+    }
+}
@@ -0,0 +1,2516 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs,
+    fs::File,
+    io,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::Arc,
+};
+
+use execute::command_args;
+use path_absolutize::Absolutize;
+
+use crate::{
+    archive_entry::UnsafeEntryChecker,
+    archiver::{get_temporary_prefix, is_streamable, try_delete_file},
+    command::{
+        create_input_pipe, is_executable, is_executable_file, is_stdio, run, run_output,
+        run_pipeline, run_pipeline_inspected, run_pipeline_watched, run_to_writer,
+        supports_7z_memory_limit, supports_xz_parallel_decompression, type_password, STDIO_PATH,
+    },
+    disk_space::{check_available_space, get_directory_usage},
+    error::IoResultExt,
+    priority::{check_nice, set_priority},
+    tar_stream::TarStreamCounter,
+    volume::{
+        get_possibly_missing_volume_path, resolve_volumes, VolumeReader, FIRST_VOLUME_EXTENSION,
+    },
+    ArchiveEntry, ArchiveFormat, EntryType, ExecutablePaths, IoPriority, Report, XcompressError,
+};
+
+/// How many bytes at the beginning of stdin are read to recognize the format. A compressed tar header needs some data to be decompressed.
+const STDIN_HEADER_SIZE: u64 = 65536;
+
+/// zstd refuses to decompress the frames whose window is larger than 128 MiB, like the ones created with `--long`, unless the limit is raised.
+const ZSTD_LONG_OPTION: &str = "--long=31";
+
+const ZSTD_FRAME_MAGIC: u32 = 0xFD2F_B528;
+
+/// The magic numbers of the skippable frames are from `0x184D2A50` to `0x184D2A5F`.
+const ZSTD_SKIPPABLE_FRAME_MAGIC: u32 = 0x184D_2A50;
+
+/// What to do if an extracted file already exists.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum OverwritePolicy {
+    /// Replace the existing file.
+    #[default]
+    Always,
+    /// Keep the existing file and skip the extracted one.
+    Never,
+    /// Keep both files. The extracted file, or the existing file for the TAR formats, is renamed.
+    Rename,
+    /// Replace the existing file only if the extracted one is newer.
+    Newer,
+    /// Ask by the function set with `Extractor::ask_overwrite`, or let 7z, unzip and unrar ask the user on the terminal. A file which nobody is asked about is skipped.
+    Ask,
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = XcompressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "always" => Ok(OverwritePolicy::Always),
+            "never" => Ok(OverwritePolicy::Never),
+            "rename" => Ok(OverwritePolicy::Rename),
+            "newer" => Ok(OverwritePolicy::Newer),
+            "ask" => Ok(OverwritePolicy::Ask),
+            _ => Err(XcompressError::InvalidOption(format!(
+                "Unknown overwrite policy {s:?}. Accepted values: always, never, rename, newer, \
+                 ask."
+            ))),
+        }
+    }
+}
+
+/// Decide whether to overwrite an existing file, for `OverwritePolicy::Ask`.
+#[derive(Clone)]
+struct AskOverwrite(Arc<dyn Fn(&Path) -> bool + Send + Sync>);
+
+impl Debug for AskOverwrite {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("AskOverwrite")
+    }
+}
+
+/// Extract, list or test an existing archive file by the external programs.
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    input_path:       PathBuf,
+    /// The beginning of stdin, which has been read to recognize the format, if the input path is `-`.
+    stdin_header:     Option<Vec<u8>>,
+    /// All volumes of a split archive in order, if the input path is one of them.
+    volume_paths:     Vec<PathBuf>,
+    format:           Option<ArchiveFormat>,
+    password:         Option<String>,
+    overwrite:        OverwritePolicy,
+    ask_overwrite:    Option<AskOverwrite>,
+    safe:             bool,
+    check_space:      bool,
+    smart:            bool,
+    max_output_size:  Option<u64>,
+    max_ratio:        Option<f64>,
+    max_entries:      Option<u64>,
+    members:          Vec<String>,
+    include:          Vec<String>,
+    exclude:          Vec<String>,
+    strip_components: usize,
+    memory_limit:     Option<u64>,
+    nice:             Option<i32>,
+    ionice:           Option<IoPriority>,
+    threads:          usize,
+    quiet:            bool,
+    executable_paths: ExecutablePaths,
+}
+
+impl Extractor {
+    /// The format of the archive file is determined by its content and its file name extension, unless it is assigned by the `format` method.
+    ///
+    /// If the input path is a volume of a split archive, like `foo.tar.zst.002` or `foo.part3.rar`, the archive is opened by its first volume, or by its last volume `foo.zip` for a split ZIP archive, and the other volumes are found beside it. A missing volume is reported before running the programs, as far as the naming scheme tells. The volumes of 7Z and RAR are read by their programs, the volumes of ZIP are read by 7z because unzip cannot read them, and the volumes of the other formats are joined as a stream.
+    #[inline]
+    pub fn open<P: AsRef<Path>>(input_path: P) -> Result<Extractor, XcompressError> {
+        let input_path = input_path.as_ref();
+
+        let (input_path, stdin_header, volume_paths) = if is_stdio(input_path) {
+            (input_path.to_path_buf(), Some(read_stdin_header()?), Vec::new())
+        } else {
+            let input_path = input_path.canonicalize().with_path(input_path)?;
+            let (input_path, volume_paths) = resolve_volumes(input_path.as_path())?;
+
+            (input_path, None, volume_paths)
+        };
+
+        Ok(Extractor {
+            input_path,
+            stdin_header,
+            volume_paths,
+            format: None,
+            password: None,
+            overwrite: OverwritePolicy::Always,
+            ask_overwrite: None,
+            safe: true,
+            check_space: true,
+            smart: false,
+            max_output_size: None,
+            max_ratio: None,
+            max_entries: None,
+            members: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            strip_components: 0,
+            memory_limit: None,
+            nice: None,
+            ionice: None,
+            threads: num_cpus::get(),
+            quiet: false,
+            executable_paths: ExecutablePaths::default(),
+        })
+    }
+
+    #[inline]
+    pub fn format(mut self, format: ArchiveFormat) -> Self {
+        self.format = Some(format);
+
+        self
+    }
+
+    /// Decrypt the archive. Only 7Z, ZIP and RAR are supported.
+    #[inline]
+    pub fn password<S: Into<String>>(mut self, password: S) -> Self {
+        self.password = Some(password.into());
+
+        self
+    }
+
+    /// What to do if an extracted file already exists. The default is to replace it. `OverwritePolicy::Ask` cannot be used with a password, which is typed on the terminal of the program.
+    #[inline]
+    pub fn overwrite(mut self, overwrite: OverwritePolicy) -> Self {
+        self.overwrite = overwrite;
+
+        self
+    }
+
+    /// Decide whether to overwrite an existing file with `OverwritePolicy::Ask`, instead of asking the user on the terminal. The function gets the path of the existing file, and returns `true` to overwrite it. It is not called for the files extracted by 7z, unzip and unrar themselves.
+    #[inline]
+    pub fn ask_overwrite<F: Fn(&Path) -> bool + Send + Sync + 'static>(mut self, ask: F) -> Self {
+        self.ask_overwrite = Some(AskOverwrite(Arc::new(ask)));
+
+        self
+    }
+
+    /// Refuse to extract an archive file whose entries can be written outside the destination directory, by checking its listing first. The default is `true`.
+    #[inline]
+    pub fn safe(mut self, safe: bool) -> Self {
+        self.safe = safe;
+
+        self
+    }
+
+    /// Refuse to extract if the filesystem does not have enough free space for the uncompressed size of the archive file. The default is `true`.
+    #[inline]
+    pub fn check_space(mut self, check_space: bool) -> Self {
+        self.check_space = check_space;
+
+        self
+    }
+
+    /// Avoid scattering the entries in the output directory. Unless all entries are in a single top-level directory, the archive file is extracted into a new directory named after it, like `foo` for `foo.tar.gz`, or `foo (1)` if `foo` exists. The default is `false`.
+    #[inline]
+    pub fn smart(mut self, smart: bool) -> Self {
+        self.smart = smart;
+
+        self
+    }
+
+    /// Abort the extraction if the extracted data is larger than this size in bytes. The partially extracted files are removed.
+    #[inline]
+    pub fn max_output_size(mut self, size: u64) -> Self {
+        self.max_output_size = Some(size);
+
+        self
+    }
+
+    /// Abort the extraction if the extracted data is larger than this multiple of the size of the archive file. The partially extracted files are removed.
+    #[inline]
+    pub fn max_ratio(mut self, ratio: f64) -> Self {
+        self.max_ratio = Some(ratio);
+
+        self
+    }
+
+    /// Abort the extraction if the archive file contains more entries than this. The partially extracted files are removed.
+    #[inline]
+    pub fn max_entries(mut self, entries: u64) -> Self {
+        self.max_entries = Some(entries);
+
+        self
+    }
+
+    /// Only extract these entries. A directory includes the entries in it. The single-file formats are not supported.
+    #[inline]
+    pub fn members<I: IntoIterator<Item = S>, S: Into<String>>(mut self, members: I) -> Self {
+        self.members = members.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Only extract the entries matching any of the glob patterns, like `config/*.yaml`. The patterns are interpreted by the programs, so `*` may also match `/`.
+    #[inline]
+    pub fn include<I: IntoIterator<Item = S>, S: Into<String>>(mut self, patterns: I) -> Self {
+        self.include = patterns.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Do not extract the entries matching any of the glob patterns.
+    #[inline]
+    pub fn exclude<I: IntoIterator<Item = S>, S: Into<String>>(mut self, patterns: I) -> Self {
+        self.exclude = patterns.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Remove the leading components from the paths of the extracted entries, like `tar --strip-components`. The entries with fewer components are not extracted. For 7Z, ZIP and RAR, the archive file is extracted into a temporary directory first, and the entries are moved out of it.
+    #[inline]
+    pub fn strip_components(mut self, n: usize) -> Self {
+        self.strip_components = n;
+
+        self
+    }
+
+    /// Limit the memory usage of the programs in bytes. Only xz, lzma, zstd and 7z are affected, and 7z needs to be 7-Zip 22.00 or later. The extraction fails if it needs more memory.
+    #[inline]
+    pub fn memory_limit(mut self, size: u64) -> Self {
+        self.memory_limit = Some(size);
+
+        self
+    }
+
+    /// The niceness of the programs, from -20 (the highest priority) to 19 (the lowest priority).
+    #[inline]
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+
+        self
+    }
+
+    /// The I/O scheduling class and priority of the programs.
+    #[inline]
+    pub fn ionice(mut self, ionice: IoPriority) -> Self {
+        self.ionice = Some(ionice);
+
+        self
+    }
+
+    /// The number of threads which the programs can use. `0` means the number of CPUs, which is the default.
+    #[inline]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = if threads == 0 { num_cpus::get() } else { threads };
+
+        self
+    }
+
+    /// Make the programs output less messages.
+    #[inline]
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+
+        self
+    }
+
+    #[inline]
+    pub fn executable_paths(mut self, executable_paths: ExecutablePaths) -> Self {
+        self.executable_paths = executable_paths;
+
+        self
+    }
+
+    /// Whether a password has been set.
+    #[inline]
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+
+    #[inline]
+    pub fn input_path(&self) -> &Path {
+        self.input_path.as_path()
+    }
+
+    /// Get the assigned format, or recognize it from the archive file.
+    #[inline]
+    pub fn archive_format(&self) -> Result<ArchiveFormat, XcompressError> {
+        match self.format {
+            Some(format) => Ok(format),
+            None => match self.stdin_header.as_deref() {
+                Some(header) => {
+                    ArchiveFormat::get_archive_format_from_header(header, &self.executable_paths)
+                },
+                None => ArchiveFormat::get_archive_format_from_file(
+                    self.input_path.as_path(),
+                    &self.executable_paths,
+                ),
+            },
+        }
+    }
+
+    fn get_format_and_password(&self) -> Result<(ArchiveFormat, &str), XcompressError> {
+        let format = self.archive_format()?;
+
+        check_nice(self.nice)?;
+
+        if self.password.is_some() && !format.supports_password() {
+            return Err(XcompressError::UnsupportedOption {
+                option: "password",
+                format,
+                supported: "7Z, ZIP and RAR",
+            });
+        }
+
+        if self.is_stdin() && !is_streamable(format) {
+            return Err(XcompressError::UnsupportedOption {
+                option: "stdin",
+                format,
+                supported: "TAR, Z, GZIP, BZIP2, LZ, XZ, LZMA, ZSTD and their TAR formats",
+            });
+        }
+
+        Ok((format, self.password.as_deref().unwrap_or("")))
+    }
+}
+
+impl Extractor {
+    /// Extract the archive file into a directory, which is created if it does not exist. A single-file format, like GZIP, is decompressed into the directory with the file stem of the archive file.
+    ///
+    /// If the archive is read from stdin, the entries cannot be listed beforehand, so they are not counted before extracting, and the entries of a TAR stream are checked for unsafe paths while being extracted instead. The extraction is stopped at the first unsafe entry, and the entries before it are kept.
+    pub fn extract_to<P: AsRef<Path>>(&self, output_path: P) -> Result<Report, XcompressError> {
+        self.extract(output_path.as_ref()).map_err(|error| self.map_missing_volume(error))
+    }
+
+    fn extract(&self, output_path: &Path) -> Result<Report, XcompressError> {
+        let (format, password) = self.get_format_and_password()?;
+
+        // the password is typed through the terminal of the program, which cannot be answered by the user at the same time
+        if self.overwrite == OverwritePolicy::Ask && !password.is_empty() {
+            return Err(XcompressError::InvalidOption(String::from(
+                "The user cannot be asked about overwriting files while the password is given.",
+            )));
+        }
+
+        self.check_selection(format)?;
+
+        let limited = self.is_limited();
+
+        if self.is_stdin() && self.max_ratio.is_some() {
+            return Err(XcompressError::InvalidOption(String::from(
+                "The compression ratio cannot be limited for the archive from stdin.",
+            )));
+        }
+
+        // a single-file format is decompressed into a single file anyway
+        let smart = self.smart && !format.is_single_file();
+
+        if self.is_stdin() && smart {
+            return Err(XcompressError::InvalidOption(String::from(
+                "The archive from stdin cannot be inspected for the smart extraction.",
+            )));
+        }
+
+        let mut uncompressed_size = None;
+        let mut single_root = true;
+
+        if format.is_single_file() {
+            if self.check_space && !self.is_stdin() {
+                uncompressed_size = self.get_uncompressed_size(format);
+            }
+        } else if self.is_stdin() {
+            // stdin can only be read once
+        } else if self.safe || limited || self.check_space || smart {
+            let entries = self.list()?;
+
+            single_root = has_single_root_directory(&entries, self.strip_components);
+
+            // the size of an entry may be absent in the listing
+            if entries
+                .iter()
+                .all(|entry| entry.size.is_some() || entry.entry_type != EntryType::File)
+            {
+                uncompressed_size = Some(entries.iter().filter_map(|entry| entry.size).sum());
+            }
+
+            if self.safe {
+                let unsafe_entries = ArchiveEntry::find_unsafe_entries(&entries);
+
+                if !unsafe_entries.is_empty() {
+                    return Err(XcompressError::UnsafeEntries(unsafe_entries));
+                }
+            }
+
+            if limited {
+                // the sizes in the listing may be missing or fake, so the limits are checked again while extracting
+                let size = entries.iter().filter_map(|entry| entry.size).sum();
+
+                self.check_limits(self.get_input_size()?, entries.len() as u64, size)?;
+            }
+        }
+
+        let wrapped_output_path;
+
+        let mut warnings = Vec::new();
+
+        let output_path = if smart && !single_root {
+            wrapped_output_path = get_unused_directory_path(output_path, self.get_archive_stem());
+
+            warnings.push(format!(
+                "The archive file has more than one top-level entry, so it is extracted into \
+                 {wrapped_output_path:?}."
+            ));
+
+            wrapped_output_path.as_path()
+        } else {
+            output_path
+        };
+
+        // the size of stdin is unknown, which is no surprise
+        if self.check_space && !self.is_stdin() {
+            match uncompressed_size {
+                Some(size) => check_available_space(
+                    output_path.absolutize().with_path(output_path)?.as_ref(),
+                    size,
+                )?,
+                None => warnings.push(String::from(
+                    "Warning: The size of the extracted data is unknown, so the free space is not \
+                     checked.",
+                )),
+            }
+        }
+
+        let mut output_path_created = false;
+
+        let output_path = match output_path.canonicalize() {
+            Ok(output_path) => {
+                if !output_path.is_dir() {
+                    return Err(XcompressError::InvalidPath {
+                        path:   output_path,
+                        reason: "is not a directory",
+                    });
+                }
+
+                output_path
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir_all(output_path).with_path(output_path)?;
+
+                output_path_created = true;
+
+                output_path.canonicalize().with_path(output_path)?
+            },
+            Err(error) => return Err(error).with_path(output_path),
+        };
+
+        // with limits, the partially extracted files can be removed together with the temporary directory
+        let result = if (limited && !format.is_single_file())
+            || (self.strip_components > 0 && !is_tar_format(format))
+            || self.is_overwrite_deferred(format)
+        {
+            self.extract_via_temporary_directory(
+                format,
+                password,
+                output_path.as_path(),
+                &mut warnings,
+            )
+        } else {
+            self.extract_archive(format, password, output_path.as_path(), &mut warnings)
+        };
+
+        if result.is_err() && output_path_created {
+            // the partially extracted files are in the directory created by us
+            if fs::remove_dir_all(output_path.as_path()).is_err() {}
+        }
+
+        result.map(|output_path| Report {
+            format,
+            output_path,
+            warnings,
+        })
+    }
+
+    /// Return the path of the extracted file for a single-file format, or the output directory.
+    fn extract_archive(
+        &self,
+        format: ArchiveFormat,
+        password: &str,
+        output_path: &Path,
+        warnings: &mut Vec<String>,
+    ) -> Result<PathBuf, XcompressError> {
+        let executable_paths = &self.executable_paths;
+        let input_path = self.input_path.as_path();
+        let stream_path = self.get_stream_path(format);
+        let threads = self.threads.to_string();
+        let threads = threads.as_str();
+
+        let tar_extract_command = |archive_path: &Path| {
+            let mut command = command_args!(&executable_paths.tar_path, "-x", "-C", output_path);
+
+            match self.get_program_overwrite_policy(format) {
+                OverwritePolicy::Never => {
+                    command.arg("--skip-old-files");
+                },
+                OverwritePolicy::Newer => {
+                    command.arg("--keep-newer-files");
+                },
+                // `Rename` and `Ask` are deferred
+                OverwritePolicy::Always | OverwritePolicy::Rename | OverwritePolicy::Ask => (),
+            }
+
+            if !self.quiet {
+                command.arg("-v");
+            }
+
+            command.arg("-f");
+            command.arg(archive_path);
+
+            self.add_tar_selection_options(&mut command);
+
+            command
+        };
+
+        match format {
+            ArchiveFormat::TarZ
+            | ArchiveFormat::TarGzip
+            | ArchiveFormat::TarBzip2
+            | ArchiveFormat::TarLz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarZstd => {
+                let compressor_format = format.to_compressor_format().unwrap();
+
+                // with limits or from stdin, the decompressed TAR stream is piped through this process to be inspected
+                let command1 =
+                    self.create_piped_decompress_command(compressor_format).or_else(|| {
+                        self.is_inspected(format)
+                            .then(|| self.create_decompress_command(compressor_format))
+                    });
+
+                match command1 {
+                    Some(mut command1) => {
+                        let mut command2 = tar_extract_command(Path::new(STDIO_PATH));
+
+                        self.run_extract_commands(
+                            &mut [&mut command1, &mut command2],
+                            format,
+                            output_path,
+                        )?;
+                    },
+                    None => {
+                        let mut command = tar_extract_command(stream_path);
+
+                        command.arg("-I");
+                        command.arg(self.get_decompress_program(compressor_format));
+
+                        self.run_extract_commands(&mut [&mut command], format, output_path)?;
+                    },
+                }
+            },
+            ArchiveFormat::Tar7z => {
+                let mut command1 =
+                    command_args!(&executable_paths.p7z_path, "x", "-so", format!("-mmt{threads}"));
+
+                self.add_7z_memory_limit_option(&mut command1, format)?;
+
+                self.add_7z_password_option(&mut command1, password);
+
+                command1.arg(input_path);
+
+                let mut command2 = tar_extract_command(Path::new(STDIO_PATH));
+
+                self.run_extract_commands(
+                    &mut [&mut command1, &mut command2],
+                    format,
+                    output_path,
+                )?;
+            },
+            ArchiveFormat::Tar => {
+                let archive_path =
+                    if self.is_inspected(format) { Path::new(STDIO_PATH) } else { stream_path };
+
+                let mut command = tar_extract_command(archive_path);
+
+                self.run_extract_commands(&mut [&mut command], format, output_path)?;
+            },
+            ArchiveFormat::Z
+            | ArchiveFormat::Gzip
+            | ArchiveFormat::Bzip2
+            | ArchiveFormat::Lz
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Lzma
+            | ArchiveFormat::Zstd => {
+                if self.is_stdin() {
+                    return Err(XcompressError::InvalidOption(String::from(
+                        "The file name of the data decompressed from stdin is unknown. Decompress \
+                         it to stdout instead.",
+                    )));
+                }
+
+                let file_path =
+                    get_single_file_output_path(self.get_base_path().as_path(), output_path)?;
+
+                let file_path = match self.resolve_existing_file(file_path, warnings)? {
+                    (file_path, true) => file_path,
+                    (file_path, false) => return Ok(file_path),
+                };
+
+                let mut command = self.create_decompress_command(format);
+
+                self.set_priority(&mut [&mut command]);
+                self.connect_input(&mut command, format)?;
+
+                let mut file = File::create(file_path.as_path()).with_path(file_path.as_path())?;
+
+                let result = if self.is_limited() {
+                    let input_size = self.get_input_size()?;
+
+                    run_to_writer(&mut command, self.quiet, &mut file, &mut |size| {
+                        self.check_limits(input_size, 0, size)
+                    })
+                } else {
+                    command.stdout(file);
+
+                    run(&mut command, self.quiet)
+                };
+
+                if let Err(error) = result {
+                    try_delete_file(file_path.as_path());
+
+                    return Err(error);
+                }
+
+                return Ok(file_path);
+            },
+            ArchiveFormat::P7z => {
+                let mut command = self.create_7z_extract_command(format, password, output_path)?;
+
+                self.run_extract_commands(&mut [&mut command], format, output_path)?;
+            },
+            // unzip cannot read a split ZIP archive, but 7z can
+            ArchiveFormat::Zip if self.is_split() => {
+                let mut command = self.create_7z_extract_command(format, password, output_path)?;
+
+                self.run_extract_commands(&mut [&mut command], format, output_path)?;
+            },
+            ArchiveFormat::Zip => {
+                let mut command = command_args!(&executable_paths.unzip_path);
+
+                self.add_unzip_password_option(&mut command, password);
+
+                if self.quiet {
+                    command.arg("-qq");
+                }
+
+                command.args(["-O", "UTF-8"]);
+
+                match self.get_program_overwrite_policy(format) {
+                    OverwritePolicy::Always | OverwritePolicy::Rename => {
+                        command.arg("-o");
+                    },
+                    OverwritePolicy::Never => {
+                        command.arg("-n");
+                    },
+                    OverwritePolicy::Newer => {
+                        command.args(["-u", "-o"]);
+                    },
+                    // unzip asks by itself
+                    OverwritePolicy::Ask => (),
+                }
+
+                command.arg(input_path);
+
+                self.add_unzip_selection_options(&mut command)?;
+
+                command.arg("-d");
+                command.arg(output_path);
+
+                self.run_extract_commands(&mut [&mut command], format, output_path)?;
+            },
+            ArchiveFormat::Rar => {
+                let mut command = command_args!(self.get_unrar_program(), "x");
+
+                match self.get_program_overwrite_policy(format) {
+                    OverwritePolicy::Always | OverwritePolicy::Rename => {
+                        command.arg("-o+");
+                    },
+                    OverwritePolicy::Never => {
+                        command.arg("-o-");
+                    },
+                    OverwritePolicy::Newer => {
+                        command.args(["-u", "-o+"]);
+                    },
+                    // unrar asks by itself
+                    OverwritePolicy::Ask => (),
+                }
+
+                command.arg(format!("-mt{threads}"));
+
+                self.add_rar_password_option(&mut command, password);
+
+                if self.quiet {
+                    command.arg("-idq");
+                }
+
+                for pattern in self.exclude.iter() {
+                    command.arg(format!("-x{pattern}"));
+                }
+
+                command.arg(input_path);
+
+                for member in self.members.iter() {
+                    let member = member.trim_end_matches('/');
+
+                    command.arg(member);
+                    command.arg(format!("{member}/*"));
+                }
+
+                command.args(self.include.iter());
+
+                // unrar takes the last argument as the output directory only if it ends with a path separator
+                let mut output_dir = output_path.as_os_str().to_os_string();
+
+                output_dir.push("/");
+
+                command.arg(output_dir);
+
+                self.run_extract_commands(&mut [&mut command], format, output_path)?;
+            },
+        }
+
+        Ok(output_path.to_path_buf())
+    }
+
+    /// The file name of the archive without the extensions, like `foo` for `foo.tar.gz`, `foo.tar.zst.001` or `foo.part1.rar`.
+    fn get_archive_stem(&self) -> String {
+        let base_path = self.get_base_path();
+
+        let file_stem = match base_path.file_stem() {
+            Some(file_stem) => file_stem.to_string_lossy(),
+            None => return String::from("archive"),
+        };
+
+        let lowercase_file_stem = file_stem.to_ascii_lowercase();
+
+        if lowercase_file_stem.ends_with(".tar") {
+            return String::from(&file_stem[..file_stem.len() - 4]);
+        }
+
+        if let Some(index) = lowercase_file_stem.rfind(".part") {
+            if self.is_split()
+                && lowercase_file_stem[index + 5..].bytes().all(|b| b.is_ascii_digit())
+            {
+                return String::from(&file_stem[..index]);
+            }
+        }
+
+        file_stem.into_owned()
+    }
+
+    /// The members are matched literally, and the include patterns are wildcards. The leading components are not stripped here.
+    fn add_tar_selection_options(&self, command: &mut Command) {
+        if self.strip_components > 0 {
+            command.arg(format!("--strip-components={}", self.strip_components));
+        }
+
+        for pattern in self.exclude.iter() {
+            command.arg(format!("--exclude={pattern}"));
+        }
+
+        if !self.members.is_empty() {
+            command.arg("--no-wildcards");
+            command.args(self.members.iter());
+        }
+
+        if !self.include.is_empty() {
+            command.arg("--wildcards");
+            command.args(self.include.iter());
+        }
+    }
+
+    /// unzip fails if a pattern matches nothing, and matches a directory only by `dir/*`, so the members are looked up in the listing first.
+    fn add_unzip_selection_options(&self, command: &mut Command) -> Result<(), XcompressError> {
+        if !self.members.is_empty() {
+            let entries = self.list()?;
+
+            for member in self.members.iter() {
+                let member = member.trim_end_matches('/');
+                let prefix = format!("{member}/");
+                let pattern = escape_unzip_pattern(member);
+
+                let mut found = false;
+
+                if entries
+                    .iter()
+                    .any(|entry| entry.path == member && entry.entry_type != EntryType::Directory)
+                {
+                    command.arg(pattern.as_str());
+
+                    found = true;
+                }
+
+                if entries.iter().any(|entry| {
+                    entry.path.starts_with(prefix.as_str())
+                        || (entry.path == member && entry.entry_type == EntryType::Directory)
+                }) {
+                    command.arg(format!("{pattern}/*"));
+
+                    found = true;
+                }
+
+                if !found {
+                    return Err(XcompressError::InvalidOption(format!(
+                        "{member:?} is not found in the archive file."
+                    )));
+                }
+            }
+        }
+
+        command.args(self.include.iter());
+
+        if !self.exclude.is_empty() {
+            command.arg("-x");
+            command.args(self.exclude.iter());
+        }
+
+        Ok(())
+    }
+
+    /// The entries cannot be selected from a single-file format.
+    fn check_selection(&self, format: ArchiveFormat) -> Result<(), XcompressError> {
+        if !format.is_single_file() {
+            return Ok(());
+        }
+
+        let option = if !self.members.is_empty() {
+            "members"
+        } else if !self.include.is_empty() {
+            "include"
+        } else if !self.exclude.is_empty() {
+            "exclude"
+        } else if self.strip_components > 0 {
+            "strip-components"
+        } else {
+            return Ok(());
+        };
+
+        Err(XcompressError::UnsupportedOption {
+            option,
+            format,
+            supported: "the TAR formats, 7Z, ZIP and RAR",
+        })
+    }
+
+    /// Extract the archive file into a temporary directory in the output directory, and move the entries out of it. 7z, unzip and unrar cannot strip the leading components, so only the entries at the stripped depth are moved. With limits, only the temporary directory is watched, and the partially extracted files are not left in the output directory.
+    fn extract_via_temporary_directory(
+        &self,
+        format: ArchiveFormat,
+        password: &str,
+        output_path: &Path,
+        warnings: &mut Vec<String>,
+    ) -> Result<PathBuf, XcompressError> {
+        let temporary_path = output_path.join(get_temporary_prefix().trim_end_matches('.'));
+
+        fs::create_dir(temporary_path.as_path()).with_path(temporary_path.as_path())?;
+
+        // tar strips the leading components by itself
+        let depth = if is_tar_format(format) { 0 } else { self.strip_components };
+
+        let result = self
+            .extract_archive(format, password, temporary_path.as_path(), warnings)
+            .and_then(|_| {
+                self.move_stripped_entries(temporary_path.as_path(), output_path, depth, warnings)
+            });
+
+        let _ = fs::remove_dir_all(temporary_path.as_path());
+
+        result.map(|_| output_path.to_path_buf())
+    }
+
+    /// The entries with fewer components than `depth` are left in the temporary directory.
+    fn move_stripped_entries(
+        &self,
+        dir: &Path,
+        output_path: &Path,
+        depth: usize,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), XcompressError> {
+        for entry in fs::read_dir(dir).with_path(dir)? {
+            let entry = entry.with_path(dir)?;
+            let path = entry.path();
+
+            if depth == 0 {
+                self.move_entry(path.as_path(), output_path.join(entry.file_name()), warnings)?;
+            } else if entry.file_type().with_path(path.as_path())?.is_dir() {
+                self.move_stripped_entries(path.as_path(), output_path, depth - 1, warnings)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move an extracted entry into the output directory. The directories are merged, and the overwrite policy is applied to the existing files.
+    fn move_entry(
+        &self,
+        source_path: &Path,
+        target_path: PathBuf,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), XcompressError> {
+        let target_metadata = match fs::symlink_metadata(target_path.as_path()) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return fs::rename(source_path, target_path.as_path()).with_path(target_path);
+            },
+            Err(error) => return Err(error).with_path(target_path),
+        };
+
+        let source_metadata = fs::symlink_metadata(source_path).with_path(source_path)?;
+
+        if source_metadata.is_dir() && target_metadata.is_dir() {
+            for entry in fs::read_dir(source_path).with_path(source_path)? {
+                let entry = entry.with_path(source_path)?;
+
+                self.move_entry(
+                    entry.path().as_path(),
+                    target_path.join(entry.file_name()),
+                    warnings,
+                )?;
+            }
+
+            return Ok(());
+        }
+
+        if source_metadata.is_dir() || target_metadata.is_dir() {
+            return Err(XcompressError::InvalidPath {
+                path:   target_path,
+                reason: "conflicts with an extracted entry of a different type",
+            });
+        }
+
+        let (target_path, overwrite) = if self.overwrite == OverwritePolicy::Newer {
+            // compare with the extracted file rather than the archive file, like the programs do
+            let source_modified = source_metadata.modified().with_path(source_path)?;
+            let target_modified = target_metadata.modified().with_path(target_path.as_path())?;
+
+            let overwrite = source_modified > target_modified;
+
+            if !overwrite {
+                warnings.push(format!("Skip {target_path:?} because it is not older."));
+            }
+
+            (target_path, overwrite)
+        } else {
+            self.resolve_existing_file(target_path, warnings)?
+        };
+
+        if overwrite {
+            fs::rename(source_path, target_path.as_path()).with_path(target_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Put the password in the command with the option, like `-p`. The password is typed through a pseudo terminal if possible.
+    fn add_password_option(&self, command: &mut Command, option: &str, password: &str) {
+        if type_password(command, password) {
+            command.arg(option);
+        } else {
+            command.arg(format!("{option}{password}"));
+        }
+    }
+
+    #[inline]
+    fn is_stdin(&self) -> bool {
+        self.stdin_header.is_some()
+    }
+
+    /// The volumes of a numbered split archive end where the next one does not exist, so a failure of the program reading them, like `premature end` of unzstd, is probably caused by the missing last volumes.
+    fn map_missing_volume(&self, error: XcompressError) -> XcompressError {
+        match error {
+            XcompressError::ToolFailed {
+                ..
+            }
+            | XcompressError::Corrupted => {
+                match get_possibly_missing_volume_path(&self.volume_paths) {
+                    Some(volume_path) => XcompressError::MissingVolume(volume_path),
+                    None => error,
+                }
+            },
+            _ => error,
+        }
+    }
+
+    /// The programs apply the overwrite policies differently, like `tar --backup=numbered` renaming the existing file rather than the extracted one, or cannot apply some of them. Then the archive file is extracted into a temporary directory, and the policy is applied while the entries are moved out of it, in the same way as for the single-file formats.
+    #[inline]
+    fn is_overwrite_deferred(&self, format: ArchiveFormat) -> bool {
+        match self.overwrite {
+            OverwritePolicy::Rename => !format.is_single_file(),
+            OverwritePolicy::Ask => is_tar_format(format),
+            OverwritePolicy::Newer => {
+                format == ArchiveFormat::P7z || (format == ArchiveFormat::Zip && self.is_split())
+            },
+            OverwritePolicy::Always | OverwritePolicy::Never => false,
+        }
+    }
+
+    /// The policy for the program, which replaces the files in the temporary directory if the policy is deferred.
+    #[inline]
+    fn get_program_overwrite_policy(&self, format: ArchiveFormat) -> OverwritePolicy {
+        if self.is_overwrite_deferred(format) {
+            OverwritePolicy::Always
+        } else {
+            self.overwrite
+        }
+    }
+
+    #[inline]
+    fn is_split(&self) -> bool {
+        !self.volume_paths.is_empty()
+    }
+
+    fn create_7z_extract_command(
+        &self,
+        format: ArchiveFormat,
+        password: &str,
+        output_path: &Path,
+    ) -> Result<Command, XcompressError> {
+        let mut command = command_args!(
+            &self.executable_paths.p7z_path,
+            "x",
+            format!("-mmt{}", self.threads),
+            format!("-o{}", output_path.to_string_lossy())
+        );
+
+        self.add_7z_memory_limit_option(&mut command, format)?;
+
+        self.add_7z_password_option(&mut command, password);
+
+        command.arg(self.input_path.as_path());
+        command.args(self.members.iter());
+
+        for pattern in self.include.iter() {
+            command.arg(format!("-ir!{pattern}"));
+        }
+
+        for pattern in self.exclude.iter() {
+            command.arg(format!("-xr!{pattern}"));
+        }
+
+        match self.get_program_overwrite_policy(format) {
+            OverwritePolicy::Always | OverwritePolicy::Rename | OverwritePolicy::Newer => {
+                command.arg("-aoa");
+            },
+            OverwritePolicy::Never => {
+                command.arg("-aos");
+            },
+            // 7-Zip asks by itself
+            OverwritePolicy::Ask => (),
+        }
+
+        if self.quiet {
+            command.stdout(Stdio::null());
+        }
+
+        Ok(command)
+    }
+
+    #[inline]
+    fn create_7z_test_command(
+        &self,
+        format: ArchiveFormat,
+        password: &str,
+    ) -> Result<Command, XcompressError> {
+        let mut command =
+            command_args!(&self.executable_paths.p7z_path, "t", format!("-mmt{}", self.threads));
+
+        self.add_7z_memory_limit_option(&mut command, format)?;
+
+        self.add_7z_password_option(&mut command, password);
+
+        command.arg(self.input_path.as_path());
+
+        Ok(command)
+    }
+
+    /// Whether the programs read the archive from their stdin, which is fed with stdin of this process or with the joined volumes.
+    #[inline]
+    fn is_piped(&self, format: ArchiveFormat) -> bool {
+        self.is_stdin() || (self.is_split() && is_streamable(format))
+    }
+
+    /// The path of the archive file given to the programs, which is `-` if the archive is piped.
+    #[inline]
+    fn get_stream_path(&self, format: ArchiveFormat) -> &Path {
+        if self.is_piped(format) {
+            Path::new(STDIO_PATH)
+        } else {
+            self.input_path.as_path()
+        }
+    }
+
+    /// The path of the archive file without the extension of the first volume, like `foo.gz` for `foo.gz.001`.
+    #[inline]
+    fn get_base_path(&self) -> PathBuf {
+        if self.is_split()
+            && self
+                .input_path
+                .extension()
+                .is_some_and(|extension| extension == FIRST_VOLUME_EXTENSION)
+        {
+            self.input_path.with_extension("")
+        } else {
+            self.input_path.clone()
+        }
+    }
+
+    /// Make the program read the archive from stdin if it is piped. The beginning of stdin which has been read is written first.
+    #[inline]
+    fn connect_input(
+        &self,
+        command: &mut Command,
+        format: ArchiveFormat,
+    ) -> Result<(), XcompressError> {
+        if self.is_piped(format) {
+            command.stdin(create_input_pipe(self.open_input()?)?);
+        }
+
+        Ok(())
+    }
+
+    /// Open the archive as a stream, which is stdin, the joined volumes, or the archive file.
+    fn open_input(&self) -> Result<Box<dyn Read + Send>, XcompressError> {
+        if let Some(header) = self.stdin_header.as_ref() {
+            Ok(Box::new(io::Cursor::new(header.clone()).chain(io::stdin())))
+        } else if self.is_split() {
+            Ok(Box::new(VolumeReader::open(&self.volume_paths)?))
+        } else {
+            Ok(Box::new(File::open(self.input_path.as_path()).with_path(&self.input_path)?))
+        }
+    }
+
+    #[inline]
+    fn set_priority(&self, commands: &mut [&mut Command]) {
+        for command in commands {
+            set_priority(command, self.nice, self.ionice);
+        }
+    }
+
+    /// Add the memory usage limit for decompression to the command of xz, lzma or zstd. zstd needs a larger limit than its default one for the large windows anyway.
+    fn add_memory_limit_option(&self, command: &mut Command, format: ArchiveFormat) {
+        match (format, self.memory_limit) {
+            (ArchiveFormat::Xz | ArchiveFormat::Lzma, Some(memory_limit)) => {
+                command.arg(format!("--memlimit-decompress={memory_limit}"));
+            },
+            (ArchiveFormat::Zstd, Some(memory_limit)) => {
+                command.arg(format!("--memory={memory_limit}"));
+            },
+            (ArchiveFormat::Zstd, None) => {
+                command.arg(ZSTD_LONG_OPTION);
+            },
+            _ => (),
+        }
+    }
+
+    /// p7zip does not support `-mmemuse`, so it is an error only if the memory limit is set.
+    fn add_7z_memory_limit_option(
+        &self,
+        command: &mut Command,
+        format: ArchiveFormat,
+    ) -> Result<(), XcompressError> {
+        if let Some(memory_limit) = self.memory_limit {
+            if !supports_7z_memory_limit(&self.executable_paths.p7z_path) {
+                return Err(XcompressError::UnsupportedOption {
+                    option: "memory-limit",
+                    format,
+                    supported: "XZ, LZMA, ZSTD, and 7Z with 7-Zip 22.00 or later",
+                });
+            }
+
+            command.arg(format!("-mmemuse={memory_limit}b"));
+        }
+
+        Ok(())
+    }
+
+    fn add_7z_password_option(&self, command: &mut Command, password: &str) {
+        if password.is_empty() {
+            command.arg("-p");
+        } else {
+            self.add_password_option(command, "-p", password);
+        }
+    }
+
+    /// zipinfo does not show the targets of the symlinks, which are stored as the contents of the entries, so they are read by `unzip -p`.
+    fn read_zip_symlink_targets(
+        &self,
+        entries: &mut [ArchiveEntry],
+        password: &str,
+    ) -> Result<(), XcompressError> {
+        for entry in entries.iter_mut() {
+            if entry.entry_type != EntryType::Symlink || entry.link_target.is_some() {
+                continue;
+            }
+
+            // the target cannot be read without the password, so the symlink stays unknown
+            if entry.encrypted && password.is_empty() {
+                continue;
+            }
+
+            let mut command = command_args!(&self.executable_paths.unzip_path, "-p");
+
+            self.add_unzip_password_option(&mut command, password);
+
+            command.arg(self.input_path.as_path());
+            command.arg(escape_unzip_pattern(entry.path.as_str()));
+
+            command.stdout(Stdio::piped());
+
+            self.set_priority(&mut [&mut command]);
+
+            let output = run_output(&mut command, true)?;
+
+            entry.link_target = Some(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+
+        Ok(())
+    }
+
+    fn add_unzip_password_option(&self, command: &mut Command, password: &str) {
+        // unzip asks for the password if it is not in the arguments
+        if password.is_empty() || !type_password(command, password) {
+            command.args(["-P", password]);
+        }
+    }
+
+    fn add_rar_password_option(&self, command: &mut Command, password: &str) {
+        if password.is_empty() {
+            command.arg("-p-");
+        } else {
+            self.add_password_option(command, "-p", password);
+        }
+    }
+
+    /// Get the uncompressed size of a single-file format from the file, if the format records it.
+    fn get_uncompressed_size(&self, format: ArchiveFormat) -> Option<u64> {
+        // the size is recorded at the end of the stream, which is in the last volume
+        if !self.volume_paths.is_empty() {
+            return None;
+        }
+
+        let input_path = self.input_path.as_path();
+
+        match format {
+            ArchiveFormat::Gzip => read_gzip_uncompressed_size(input_path).ok().flatten(),
+            ArchiveFormat::Zstd => read_zstd_content_size(input_path).ok().flatten(),
+            ArchiveFormat::Xz => {
+                let mut command =
+                    command_args!(&self.executable_paths.xz_path, "--robot", "--list", input_path);
+
+                command.stdout(Stdio::piped());
+
+                let output = run_output(&mut command, true).ok()?;
+
+                // e.g. `file\t1\t1\t1256\t50000000\t0.025\tCRC64\t0`
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.strip_prefix("file\t"))
+                    .and_then(|line| line.split('\t').nth(3))
+                    .and_then(|size| size.parse().ok())
+            },
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn is_limited(&self) -> bool {
+        self.max_output_size.is_some() || self.max_ratio.is_some() || self.max_entries.is_some()
+    }
+
+    /// Whether the TAR stream is piped through this process, to be counted for the limits, or to be checked for unsafe entries if it is from stdin.
+    #[inline]
+    fn is_inspected(&self, format: ArchiveFormat) -> bool {
+        self.is_limited() || (self.safe && self.is_stdin() && is_tar_format(format))
+    }
+
+    /// The size of stdin is unknown, so it is `0`. The size of a split archive is the total size of its volumes.
+    fn get_input_size(&self) -> Result<u64, XcompressError> {
+        if self.is_stdin() {
+            return Ok(0);
+        }
+
+        if self.volume_paths.is_empty() {
+            return Ok(self.input_path.metadata().with_path(self.input_path.as_path())?.len());
+        }
+
+        let mut size = 0;
+
+        for volume_path in self.volume_paths.iter() {
+            size += volume_path.metadata().with_path(volume_path)?.len();
+        }
+
+        Ok(size)
+    }
+
+    fn check_limits(&self, input_size: u64, entries: u64, size: u64) -> Result<(), XcompressError> {
+        if let Some(max_entries) = self.max_entries {
+            if entries > max_entries {
+                return Err(XcompressError::LimitExceeded("number of entries"));
+            }
+        }
+
+        if let Some(max_output_size) = self.max_output_size {
+            if size > max_output_size {
+                return Err(XcompressError::LimitExceeded("output size"));
+            }
+        }
+
+        if let Some(max_ratio) = self.max_ratio {
+            if size as f64 > input_size as f64 * max_ratio {
+                return Err(XcompressError::LimitExceeded("compression ratio"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the extracting commands. If any limit is set, the TAR stream piped into tar is counted, or the output directory, which is a new temporary directory, is watched while the other programs are running.
+    fn run_extract_commands(
+        &self,
+        commands: &mut [&mut Command],
+        format: ArchiveFormat,
+        output_path: &Path,
+    ) -> Result<(), XcompressError> {
+        self.set_priority(commands);
+
+        if !self.is_inspected(format) {
+            self.connect_input(commands[0], format)?;
+
+            return run_pipeline(commands, self.quiet, &[]).map(|_| ());
+        }
+
+        let input_size = self.get_input_size()?;
+
+        if is_tar_format(format) {
+            // tar is the last command, and reads the TAR stream from stdin
+            let index = commands.len() - 1;
+
+            // the entries from stdin are not listed beforehand, so they are checked before tar gets them
+            let mut checker = (self.safe && self.is_stdin()).then(UnsafeEntryChecker::default);
+
+            let mut counter = if checker.is_some() {
+                TarStreamCounter::recording_entries()
+            } else {
+                TarStreamCounter::default()
+            };
+
+            let mut inspector = |data: &[u8]| {
+                counter.update(data);
+
+                if let Some(checker) = checker.as_mut() {
+                    for entry in counter.new_entries.drain(..) {
+                        if let Some(reason) = checker.check(&entry) {
+                            return Err(XcompressError::UnsafeEntries(vec![(entry.path, reason)]));
+                        }
+                    }
+                }
+
+                self.check_limits(input_size, counter.entries, counter.size)
+            };
+
+            if index == 0 {
+                let mut reader = self.open_input()?;
+
+                return run_pipeline_inspected(
+                    commands,
+                    0,
+                    Some(&mut reader),
+                    self.quiet,
+                    &[],
+                    &mut inspector,
+                );
+            }
+
+            self.connect_input(commands[0], format)?;
+
+            return run_pipeline_inspected(commands, index, None, self.quiet, &[], &mut inspector);
+        }
+
+        self.connect_input(commands[0], format)?;
+
+        run_pipeline_watched(commands, self.quiet, &[], &mut || {
+            let (entries, size) = get_directory_usage(output_path).with_path(output_path)?;
+
+            self.check_limits(input_size, entries, size)
+        })
+    }
+
+    /// Apply the overwrite policy to the output file of a single-file format. Return the path to write and whether to write it.
+    fn resolve_existing_file(
+        &self,
+        file_path: PathBuf,
+        warnings: &mut Vec<String>,
+    ) -> Result<(PathBuf, bool), XcompressError> {
+        let metadata = match file_path.metadata() {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((file_path, true)),
+            Err(error) => return Err(error).with_path(file_path),
+        };
+
+        let overwrite = match self.overwrite {
+            OverwritePolicy::Always => true,
+            OverwritePolicy::Never => false,
+            OverwritePolicy::Rename => {
+                return Ok((get_renamed_file_path(file_path.as_path()), true))
+            },
+            OverwritePolicy::Newer => {
+                let input_modified = self
+                    .input_path
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .with_path(&self.input_path)?;
+                let output_modified = metadata.modified().with_path(file_path.as_path())?;
+
+                input_modified > output_modified
+            },
+            OverwritePolicy::Ask => match self.ask_overwrite.as_ref() {
+                Some(ask_overwrite) => (ask_overwrite.0)(file_path.as_path()),
+                None => false,
+            },
+        };
+
+        if !overwrite {
+            warnings.push(format!("Skip {file_path:?} because it already exists."));
+        }
+
+        Ok((file_path, overwrite))
+    }
+
+    /// List the entries in the archive file.
+    pub fn list(&self) -> Result<Vec<ArchiveEntry>, XcompressError> {
+        self.list_entries().map_err(|error| self.map_missing_volume(error))
+    }
+
+    fn list_entries(&self) -> Result<Vec<ArchiveEntry>, XcompressError> {
+        let (format, password) = self.get_format_and_password()?;
+
+        let executable_paths = &self.executable_paths;
+        let input_path = self.input_path.as_path();
+        let stream_path = self.get_stream_path(format);
+
+        let tar_list_command =
+            || command_args!(&executable_paths.tar_path, "-t", "-v", "--full-time", "-f");
+
+        let entries = match format {
+            ArchiveFormat::Tar
+            | ArchiveFormat::TarZ
+            | ArchiveFormat::TarGzip
+            | ArchiveFormat::TarBzip2
+            | ArchiveFormat::TarLz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarZstd => {
+                let mut command = tar_list_command();
+
+                command.stdout(Stdio::piped());
+
+                let output = match format.to_compressor_format().and_then(|compressor_format| {
+                    self.create_piped_decompress_command(compressor_format)
+                }) {
+                    Some(mut command1) => {
+                        command.arg("-");
+
+                        self.set_priority(&mut [&mut command1, &mut command]);
+
+                        self.connect_input(&mut command1, format)?;
+
+                        run_pipeline(&mut [&mut command1, &mut command], self.quiet, &[])?
+                    },
+                    None => {
+                        command.arg(stream_path);
+
+                        if let Some(compressor_format) = format.to_compressor_format() {
+                            command.arg("-I");
+                            command.arg(self.get_decompress_program(compressor_format));
+                        }
+
+                        self.set_priority(&mut [&mut command]);
+
+                        self.connect_input(&mut command, format)?;
+
+                        run_output(&mut command, self.quiet)?
+                    },
+                };
+
+                ArchiveEntry::parse_tar_listing(&String::from_utf8_lossy(&output.stdout))
+            },
+            ArchiveFormat::Tar7z => {
+                let mut command1 = command_args!(&executable_paths.p7z_path, "x", "-so");
+
+                self.add_7z_memory_limit_option(&mut command1, format)?;
+
+                self.add_7z_password_option(&mut command1, password);
+
+                command1.arg(input_path);
+
+                let mut command2 = tar_list_command();
+
+                command2.arg("-");
+                command2.stdout(Stdio::piped());
+
+                self.set_priority(&mut [&mut command1, &mut command2]);
+
+                self.connect_input(&mut command1, format)?;
+
+                let output = run_pipeline(&mut [&mut command1, &mut command2], self.quiet, &[])?;
+
+                ArchiveEntry::parse_tar_listing(&String::from_utf8_lossy(&output.stdout))
+            },
+            ArchiveFormat::P7z => {
+                let mut command = command_args!(&executable_paths.p7z_path, "l", "-slt");
+
+                self.add_7z_memory_limit_option(&mut command, format)?;
+
+                self.add_7z_password_option(&mut command, password);
+
+                command.arg(input_path);
+
+                command.stdout(Stdio::piped());
+
+                self.set_priority(&mut [&mut command]);
+
+                self.connect_input(&mut command, format)?;
+
+                let output = run_output(&mut command, self.quiet)?;
+
+                ArchiveEntry::parse_7z_listing(&String::from_utf8_lossy(&output.stdout))
+            },
+            ArchiveFormat::Zip => {
+                let mut command =
+                    command_args!(&executable_paths.unzip_path, "-Z", "-l", "-T", input_path);
+
+                command.stdout(Stdio::piped());
+
+                self.set_priority(&mut [&mut command]);
+
+                self.connect_input(&mut command, format)?;
+
+                let output = run_output(&mut command, self.quiet)?;
+
+                let mut entries =
+                    ArchiveEntry::parse_zipinfo_listing(&String::from_utf8_lossy(&output.stdout));
+
+                self.read_zip_symlink_targets(&mut entries, password)?;
+
+                entries
+            },
+            ArchiveFormat::Rar => {
+                let mut command = command_args!(self.get_unrar_program(), "lt");
+
+                self.add_rar_password_option(&mut command, password);
+
+                command.arg(input_path);
+
+                command.stdout(Stdio::piped());
+
+                self.set_priority(&mut [&mut command]);
+
+                self.connect_input(&mut command, format)?;
+
+                // the password is needed to read the encrypted headers, so it is reported as a wrong one to be asked for
+                let output = match run_output(&mut command, self.quiet) {
+                    Ok(output)
+                        if password.is_empty()
+                            && (is_rar_header_encrypted(&output.stdout)
+                                || is_rar_header_encrypted(&output.stderr)) =>
+                    {
+                        return Err(XcompressError::WrongPassword);
+                    },
+                    Ok(output) => output,
+                    Err(XcompressError::ToolFailed {
+                        stderr_tail, ..
+                    }) if password.is_empty()
+                        && is_rar_header_encrypted(stderr_tail.as_bytes()) =>
+                    {
+                        return Err(XcompressError::WrongPassword);
+                    },
+                    Err(error) => return Err(error),
+                };
+
+                ArchiveEntry::parse_unrar_listing(&String::from_utf8_lossy(&output.stdout))
+            },
+            ArchiveFormat::Z
+            | ArchiveFormat::Gzip
+            | ArchiveFormat::Bzip2
+            | ArchiveFormat::Lz
+            | ArchiveFormat::Xz
+            | ArchiveFormat::Lzma
+            | ArchiveFormat::Zstd => {
+                // a single-file format only has one implied member
+                let mut entry = ArchiveEntry::new(
+                    self.get_base_path().file_stem().unwrap().to_string_lossy(),
+                    EntryType::File,
+                );
+
+                if !self.is_stdin() {
+                    entry.compressed_size = Some(self.get_input_size()?);
+                }
+
+                vec![entry]
+            },
+        };
+
+        Ok(entries)
+    }
+
+    /// Decompress a single-file format to stdout, like `zcat`.
+    pub fn decompress_to_stdout(&self) -> Result<(), XcompressError> {
+        self.decompress_stream().map_err(|error| self.map_missing_volume(error))
+    }
+
+    fn decompress_stream(&self) -> Result<(), XcompressError> {
+        let (format, _) = self.get_format_and_password()?;
+
+        if !format.is_single_file() {
+            return Err(XcompressError::UnsupportedOption {
+                option: "to-stdout",
+                format,
+                supported: "Z, GZIP, BZIP2, LZ, XZ, LZMA and ZSTD",
+            });
+        }
+
+        self.check_selection(format)?;
+
+        let mut command = self.create_decompress_command(format);
+
+        self.set_priority(&mut [&mut command]);
+        self.connect_input(&mut command, format)?;
+
+        if self.is_limited() {
+            let input_size = self.get_input_size()?;
+
+            run_to_writer(&mut command, self.quiet, &mut io::stdout().lock(), &mut |size| {
+                self.check_limits(input_size, 0, size)
+            })
+        } else {
+            run(&mut command, self.quiet)
+        }
+    }
+
+    /// Check whether the archive file needs a password, by its listing. An archive file whose headers are encrypted cannot even be listed without the password, so it is also considered encrypted.
+    pub fn is_encrypted(&self) -> Result<bool, XcompressError> {
+        let format = match self.archive_format()? {
+            // the outer 7z archive is enough, so the tar archive does not have to be decompressed
+            ArchiveFormat::Tar7z => ArchiveFormat::P7z,
+            format if format.supports_password() => format,
+            _ => return Ok(false),
+        };
+
+        let mut extractor = self.clone().format(format).quiet(true);
+
+        extractor.password = None;
+
+        match extractor.list() {
+            Ok(entries) => Ok(entries.iter().any(|entry| entry.encrypted)),
+            Err(XcompressError::WrongPassword) => Ok(true),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Test the integrity of the archive file. Every stage of a pipeline is checked, so a truncated tar inside a valid compressed stream is also detected.
+    pub fn test(&self) -> Result<(), XcompressError> {
+        self.test_archive().map_err(|error| self.map_missing_volume(error))
+    }
+
+    fn test_archive(&self) -> Result<(), XcompressError> {
+        let (format, password) = self.get_format_and_password()?;
+
+        let executable_paths = &self.executable_paths;
+        let input_path = self.input_path.as_path();
+        let stream_path = self.get_stream_path(format);
+        let threads = self.threads.to_string();
+        let threads = threads.as_str();
+
+        let tar_test_command = || command_args!(&executable_paths.tar_path, "-t", "-f", "-");
+
+        let mut commands = match format {
+            ArchiveFormat::Tar => {
+                vec![command_args!(&executable_paths.tar_path, "-t", "-f", stream_path)]
+            },
+            ArchiveFormat::TarZ
+            | ArchiveFormat::TarGzip
+            | ArchiveFormat::TarBzip2
+            | ArchiveFormat::TarLz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarZstd => {
+                let compressor_format = format.to_compressor_format().unwrap();
+
+                let command1 = match self.create_piped_decompress_command(compressor_format) {
+                    Some(command) => command,
+                    None => command_args!(
+                        self.get_decompress_program(compressor_format),
+                        "-d",
+                        "-c",
+                        stream_path
+                    ),
+                };
+
+                vec![command1, tar_test_command()]
+            },
+            ArchiveFormat::Tar7z => {
+                let mut command1 =
+                    command_args!(&executable_paths.p7z_path, "x", "-so", format!("-mmt{threads}"));
+
+                self.add_7z_memory_limit_option(&mut command1, format)?;
+
+                self.add_7z_password_option(&mut command1, password);
+
+                command1.arg(input_path);
+
+                vec![command1, tar_test_command()]
+            },
+            ArchiveFormat::Z | ArchiveFormat::Gzip => {
+                if self.threads > 1 && is_executable(&executable_paths.pigz_path, "-V") {
+                    vec![command_args!(
+                        &executable_paths.pigz_path,
+                        "-t",
+                        "-p",
+                        threads,
+                        stream_path
+                    )]
+                } else {
+                    vec![command_args!(&executable_paths.gzip_path, "-t", stream_path)]
+                }
+            },
+            ArchiveFormat::Bzip2 => {
+                vec![command_args!(&executable_paths.bzip2_path, "-t", stream_path)]
+            },
+            ArchiveFormat::Lz => {
+                vec![command_args!(&executable_paths.lzip_path, "-t", stream_path)]
+            },
+            ArchiveFormat::Xz | ArchiveFormat::Lzma | ArchiveFormat::Zstd => {
+                let program = match format {
+                    ArchiveFormat::Xz => &executable_paths.xz_path,
+                    ArchiveFormat::Lzma => &executable_paths.lzma_path,
+                    _ => &executable_paths.zstd_path,
+                };
+
+                let mut command = command_args!(program, "-t");
+
+                self.add_memory_limit_option(&mut command, format);
+
+                command.arg(stream_path);
+
+                vec![command]
+            },
+            ArchiveFormat::P7z => vec![self.create_7z_test_command(format, password)?],
+            // unzip cannot read a split ZIP archive, but 7z can
+            ArchiveFormat::Zip if self.is_split() => {
+                vec![self.create_7z_test_command(format, password)?]
+            },
+            ArchiveFormat::Zip => {
+                let mut command = command_args!(&executable_paths.unzip_path, "-t");
+
+                self.add_unzip_password_option(&mut command, password);
+
+                if self.quiet {
+                    command.arg("-qq");
+                }
+
+                command.arg(input_path);
+
+                vec![command]
+            },
+            ArchiveFormat::Rar => {
+                let mut command =
+                    command_args!(self.get_unrar_program(), "t", format!("-mt{threads}"));
+
+                self.add_rar_password_option(&mut command, password);
+
+                if self.quiet {
+                    command.arg("-idq");
+                }
+
+                command.arg(input_path);
+
+                vec![command]
+            },
+        };
+
+        self.set_priority(&mut commands.iter_mut().collect::<Vec<_>>());
+        self.connect_input(&mut commands[0], format)?;
+
+        run_test_commands(&mut commands, self.quiet)
+    }
+
+    /// Create a command which decompresses the archive file to stdout and is piped to tar, if `tar -I` is not enough. That is, a multi-threaded program is available, or the program needs options, like the memory usage limit or the large window of zstd.
+    #[inline]
+    fn create_piped_decompress_command(&self, format: ArchiveFormat) -> Option<Command> {
+        self.create_parallel_decompress_command(format).or_else(|| {
+            let needs_options = match format {
+                ArchiveFormat::Zstd => true,
+                ArchiveFormat::Xz | ArchiveFormat::Lzma => self.memory_limit.is_some(),
+                _ => false,
+            };
+
+            if needs_options {
+                Some(self.create_decompress_command(format))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Create a multi-threaded command which decompresses the archive file to stdout, if the program is available.
+    fn create_parallel_decompress_command(&self, format: ArchiveFormat) -> Option<Command> {
+        if self.threads <= 1 {
+            return None;
+        }
+
+        let executable_paths = &self.executable_paths;
+        let input_path = self.get_stream_path(format);
+        let threads = self.threads.to_string();
+        let threads = threads.as_str();
+
+        match format {
+            ArchiveFormat::Z | ArchiveFormat::Gzip
+                if is_executable(&executable_paths.pigz_path, "-V") =>
+            {
+                Some(command_args!(
+                    &executable_paths.pigz_path,
+                    "-d",
+                    "-c",
+                    "-p",
+                    threads,
+                    input_path
+                ))
+            },
+            ArchiveFormat::Bzip2 if is_executable(&executable_paths.lbzip2_path, "-V") => Some(
+                command_args!(&executable_paths.lbzip2_path, "-d", "-c", "-n", threads, input_path),
+            ),
+            ArchiveFormat::Bzip2 if is_executable(&executable_paths.pbzip2_path, "-V") => {
+                Some(command_args!(
+                    &executable_paths.pbzip2_path,
+                    "-d",
+                    "-c",
+                    format!("-p{threads}"),
+                    input_path
+                ))
+            },
+            ArchiveFormat::Lz if is_executable(&executable_paths.plzip_path, "-V") => Some(
+                command_args!(&executable_paths.plzip_path, "-d", "-c", "-n", threads, input_path),
+            ),
+            ArchiveFormat::Xz => {
+                let xz_program = self.get_decompress_program(format);
+
+                // only the files with multiple blocks can be decompressed in parallel, like the ones created by `xz -T`, pxz or pixz
+                if supports_xz_parallel_decompression(xz_program) {
+                    let mut command = command_args!(xz_program, "-d", "-c", "-T", threads);
+
+                    self.add_memory_limit_option(&mut command, format);
+
+                    command.arg(input_path);
+
+                    Some(command)
+                } else if self.memory_limit.is_some() {
+                    // neither pixz nor pxz supports the memory usage limit
+                    None
+                } else if is_executable_file(&executable_paths.pixz_path) {
+                    let mut command =
+                        command_args!(&executable_paths.pixz_path, "-d", "-p", threads);
+
+                    // pixz writes to a file beside the input file unless both stdin and stdout are used
+                    if !self.is_piped(format) {
+                        command.stdin(File::open(input_path).ok()?);
+                    }
+
+                    Some(command)
+                } else if is_executable(&executable_paths.pxz_path, "-V") {
+                    Some(command_args!(
+                        &executable_paths.pxz_path,
+                        "-d",
+                        "-c",
+                        "-T",
+                        threads,
+                        input_path
+                    ))
+                } else {
+                    None
+                }
+            },
+            ArchiveFormat::Lzma
+                if self.memory_limit.is_none()
+                    && is_executable(&executable_paths.pxz_path, "-V") =>
+            {
+                Some(command_args!(
+                    &executable_paths.pxz_path,
+                    "-d",
+                    "-c",
+                    "-T",
+                    threads,
+                    "-F",
+                    "lzma",
+                    input_path
+                ))
+            },
+            ArchiveFormat::Zstd
+                if self.memory_limit.is_none()
+                    && is_executable(&executable_paths.pzstd_path, "-V") =>
+            {
+                Some(command_args!(
+                    &executable_paths.pzstd_path,
+                    "-d",
+                    "-c",
+                    "-p",
+                    threads,
+                    input_path
+                ))
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the single-threaded decompression program, which accepts `-d` and can be used by `tar -I`. The `un*` programs are preferred.
+    fn get_decompress_program(&self, format: ArchiveFormat) -> &str {
+        let executable_paths = &self.executable_paths;
+
+        let (program, fallback) = match format {
+            ArchiveFormat::Z | ArchiveFormat::Gzip => {
+                (&executable_paths.gnuzip_path, &executable_paths.gzip_path)
+            },
+            ArchiveFormat::Bzip2 => (&executable_paths.bunzip2_path, &executable_paths.bzip2_path),
+            ArchiveFormat::Lz => (&executable_paths.lunzip_path, &executable_paths.lzip_path),
+            ArchiveFormat::Xz => (&executable_paths.unxz_path, &executable_paths.xz_path),
+            ArchiveFormat::Lzma => (&executable_paths.unlzma_path, &executable_paths.lzma_path),
+            ArchiveFormat::Zstd => (&executable_paths.unzstd_path, &executable_paths.zstd_path),
+            _ => unreachable!(),
+        };
+
+        if is_executable(program, "-V") {
+            program
+        } else {
+            fallback
+        }
+    }
+
+    /// Create a command which decompresses the archive file to stdout.
+    #[inline]
+    fn create_decompress_command(&self, format: ArchiveFormat) -> Command {
+        match self.create_parallel_decompress_command(format) {
+            Some(command) => command,
+            None => {
+                let mut command = command_args!(self.get_decompress_program(format), "-d", "-c");
+
+                self.add_memory_limit_option(&mut command, format);
+
+                command.arg(self.get_stream_path(format));
+
+                command
+            },
+        }
+    }
+
+    #[inline]
+    fn get_unrar_program(&self) -> &str {
+        if is_executable(&self.executable_paths.unrar_path, "-?") {
+            &self.executable_paths.unrar_path
+        } else {
+            &self.executable_paths.rar_path
+        }
+    }
+}
+
+#[inline]
+fn get_single_file_output_path(
+    input_path: &Path,
+    output_path: &Path,
+) -> Result<PathBuf, XcompressError> {
+    let file_path = output_path.join(input_path.file_stem().unwrap());
+
+    if file_path.is_dir() {
+        return Err(XcompressError::InvalidPath {
+            path: file_path, reason: "is a directory"
+        });
+    }
+
+    if file_path == input_path {
+        return Err(XcompressError::InvalidPath {
+            path:   file_path,
+            reason: "would be overwritten by its own decompressed data. Assign another destination",
+        });
+    }
+
+    Ok(file_path)
+}
+
+/// Read the beginning of stdin to recognize the format.
+fn read_stdin_header() -> Result<Vec<u8>, XcompressError> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        return Err(XcompressError::InvalidOption(String::from(
+            "The archive cannot be read from a terminal.",
+        )));
+    }
+
+    let mut header = Vec::new();
+
+    io::stdin().lock().take(STDIN_HEADER_SIZE).read_to_end(&mut header)?;
+
+    Ok(header)
+}
+
+/// The formats extracted by tar.
+#[inline]
+fn is_tar_format(format: ArchiveFormat) -> bool {
+    matches!(
+        format,
+        ArchiveFormat::Tar
+            | ArchiveFormat::TarZ
+            | ArchiveFormat::TarGzip
+            | ArchiveFormat::TarBzip2
+            | ArchiveFormat::TarLz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::Tar7z
+            | ArchiveFormat::TarZstd
+    )
+}
+
+/// The ISIZE field in the trailer of a GZIP file is the uncompressed size modulo 2^32. Return `None` if it is obviously not the whole size.
+fn read_gzip_uncompressed_size(file_path: &Path) -> io::Result<Option<u64>> {
+    let mut file = File::open(file_path)?;
+
+    let mut magic = [0u8; 2];
+
+    if file.metadata()?.len() < 18 || file.read_exact(&mut magic).is_err() || magic != [0x1F, 0x8B]
+    {
+        return Ok(None);
+    }
+
+    let mut size = [0u8; 4];
+
+    file.seek(SeekFrom::End(-4))?;
+    file.read_exact(&mut size)?;
+
+    let size = u32::from_le_bytes(size) as u64;
+
+    // the size is modulo 2^32, and only of the last member, so it is wrapped or partial if it is smaller than the compressed data
+    if size < file.metadata()?.len() {
+        return Ok(None);
+    }
+
+    Ok(Some(size))
+}
+
+/// Sum the Frame_Content_Size fields of all ZSTD frames, like `zstd -l` does. Return `None` if a frame does not record its size or the file is not a ZSTD file.
+fn read_zstd_content_size(file_path: &Path) -> io::Result<Option<u64>> {
+    let file = File::open(file_path)?;
+
+    let file_size = file.metadata()?.len();
+
+    let mut reader = BufReader::new(file);
+
+    let mut position = 0;
+    let mut total = 0u64;
+
+    while position < file_size {
+        let magic = read_u32_le(&mut reader)?;
+
+        // skippable frames, which have no content
+        if magic & 0xFFFF_FFF0 == ZSTD_SKIPPABLE_FRAME_MAGIC {
+            let frame_size = read_u32_le(&mut reader)?;
+
+            reader.seek_relative(i64::from(frame_size))?;
+            position += 8 + u64::from(frame_size);
+
+            continue;
+        }
+
+        if magic != ZSTD_FRAME_MAGIC {
+            return Ok(None);
+        }
+
+        let mut descriptor = [0u8];
+
+        reader.read_exact(&mut descriptor)?;
+
+        let descriptor = descriptor[0];
+        let single_segment = descriptor & 0x20 != 0;
+
+        // Window_Descriptor and Dictionary_ID
+        let skipped = usize::from(!single_segment) + [0, 1, 2, 4][(descriptor & 0x03) as usize];
+
+        let field_size = match descriptor >> 6 {
+            0 if single_segment => 1,
+            0 => return Ok(None),
+            1 => 2,
+            2 => 4,
+            _ => 8,
+        };
+
+        let mut header = [0u8; 13];
+
+        reader.read_exact(&mut header[..skipped + field_size])?;
+
+        let mut bytes = [0u8; 8];
+        bytes[..field_size].copy_from_slice(&header[skipped..skipped + field_size]);
+
+        let size = u64::from_le_bytes(bytes);
+
+        total = total.saturating_add(if field_size == 2 { size + 256 } else { size });
+        position += 5 + (skipped + field_size) as u64;
+
+        // the blocks are skipped by their headers
+        loop {
+            let mut block_header = [0u8; 4];
+
+            reader.read_exact(&mut block_header[..3])?;
+
+            let block_header = u32::from_le_bytes(block_header);
+
+            let block_size = block_header >> 3;
+
+            let stored_size = match (block_header >> 1) & 0x03 {
+                // an RLE block stores a single byte
+                1 => 1,
+                3 => return Ok(None),
+                _ => block_size,
+            };
+
+            reader.seek_relative(i64::from(stored_size))?;
+            position += 3 + u64::from(stored_size);
+
+            if block_header & 0x01 != 0 {
+                break;
+            }
+        }
+
+        // Content_Checksum
+        if descriptor & 0x04 != 0 {
+            reader.seek_relative(4)?;
+            position += 4;
+        }
+    }
+
+    Ok(Some(total))
+}
+
+#[inline]
+fn read_u32_le<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Whether all entries are in a single top-level directory, after the leading components are stripped. An archive file without entries is not scattered either.
+fn has_single_root_directory(entries: &[ArchiveEntry], strip_components: usize) -> bool {
+    let mut root = None;
+    let mut is_directory = false;
+
+    for entry in entries {
+        let mut components = entry
+            .path
+            .split(['/', '\\'])
+            .filter(|component| !component.is_empty() && *component != ".")
+            .skip(strip_components);
+
+        let component = match components.next() {
+            Some(component) => component,
+            None => continue,
+        };
+
+        match root {
+            Some(root) if root != component => return false,
+            _ => root = Some(component),
+        }
+
+        if components.next().is_some() || entry.entry_type == EntryType::Directory {
+            is_directory = true;
+        }
+    }
+
+    root.is_none() || is_directory
+}
+
+/// Find an unused path like `foo (1)` for the directory `foo` in the output directory.
+fn get_unused_directory_path(output_path: &Path, name: String) -> PathBuf {
+    let directory_path = output_path.join(name.as_str());
+
+    if fs::symlink_metadata(directory_path.as_path()).is_err() {
+        return directory_path;
+    }
+
+    (1..)
+        .map(|i| output_path.join(format!("{name} ({i})")))
+        .find(|directory_path| fs::symlink_metadata(directory_path).is_err())
+        .unwrap()
+}
+
+/// Match the name literally by putting the wildcard characters of unzip in brackets.
+fn escape_unzip_pattern(name: &str) -> String {
+    let mut pattern = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        if matches!(c, '*' | '?' | '[') {
+            pattern.push('[');
+            pattern.push(c);
+            pattern.push(']');
+        } else {
+            pattern.push(c);
+        }
+    }
+
+    pattern
+}
+
+/// Find an unused path like `foo_1.txt` for `foo.txt`.
+fn get_renamed_file_path(file_path: &Path) -> PathBuf {
+    let file_stem = file_path.file_stem().unwrap().to_string_lossy();
+    let extension = file_path.extension().map(|e| format!(".{}", e.to_string_lossy()));
+    let extension = extension.as_deref().unwrap_or("");
+
+    (1..)
+        .map(|i| file_path.with_file_name(format!("{file_stem}_{i}{extension}")))
+        .find(|file_path| fs::symlink_metadata(file_path).is_err())
+        .unwrap()
+}
+
+/// unrar cannot read an archive file with encrypted headers without the password. With `-p-`, it skips the archive file like `Skipping the encrypted archive foo.rar`, or reports the encrypted headers.
+fn is_rar_header_encrypted(output: &[u8]) -> bool {
+    let output = String::from_utf8_lossy(output).to_ascii_lowercase();
+
+    output.contains("encrypted archive") || output.contains("encrypted headers")
+}
+
+/// The exit codes with which a program reports a corrupted input. `None` means the program is unknown, so any exit code is treated as corruption.
+fn get_corruption_codes(program: &str) -> Option<&'static [i32]> {
+    let program_name = Path::new(program).file_name()?.to_string_lossy();
+
+    match program_name.as_ref() {
+        "gzip" | "gunzip" | "pigz" | "xz" | "unxz" | "pixz" | "lzma" | "unlzma" | "zstd"
+        | "unzstd" | "pzstd" | "lbzip2" | "pbzip2" | "compress" | "uncompress" => Some(&[1]),
+        "bzip2" | "bunzip2" | "lzip" | "lunzip" | "plzip" | "tar" | "bsdtar" | "7z" | "7za"
+        | "7zz" | "7zr" => Some(&[2]),
+        // 9 means the central directory is not found, like in a truncated archive file, and 51 means an unexpected end of the archive file
+        "unzip" => Some(&[2, 3, 9, 51]),
+        // 3 means a CRC error
+        "unrar" | "rar" => Some(&[2, 3]),
+        _ => None,
+    }
+}
+
+/// Run the commands as a pipeline. An exit code which means a corrupted input is turned into `Corrupted`, and other failures, like a missing program or a killed process, are returned as they are.
+fn run_test_commands(commands: &mut [Command], quiet: bool) -> Result<(), XcompressError> {
+    let last_index = commands.len() - 1;
+
+    if quiet || last_index > 0 {
+        // the file list of `tar -t` is not needed
+        commands[last_index].stdout(Stdio::null());
+    }
+
+    let mut commands: Vec<&mut Command> = commands.iter_mut().collect();
+
+    match run_pipeline(&mut commands, quiet, &[]) {
+        Ok(_) => Ok(()),
+        Err(XcompressError::ToolFailed {
+            program,
+            code: Some(code),
+            ..
+        }) if get_corruption_codes(&program).map_or(true, |codes| codes.contains(&code)) => {
+            Err(XcompressError::Corrupted)
+        },
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TestDir, Archiver};
+
+    /// A ZSTD frame whose content is `hello`, stored in a raw block.
+    const ZSTD_HELLO_FRAME: [u8; 14] =
+        [0x28, 0xB5, 0x2F, 0xFD, 0x20, 0x05, 0x29, 0x00, 0x00, b'h', b'e', b'l', b'l', b'o'];
+
+    /// A ZSTD frame whose content is 300 `a`s in an RLE block. The size field has 2 bytes, which is offset by 256.
+    const ZSTD_RLE_FRAME: [u8; 11] =
+        [0x28, 0xB5, 0x2F, 0xFD, 0x60, 0x2C, 0x00, 0x63, 0x09, 0x00, b'a'];
+
+    const ZSTD_SKIPPABLE_FRAME: [u8; 12] =
+        [0x50, 0x2A, 0x4D, 0x18, 0x04, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04];
+
+    #[test]
+    fn zstd_content_size() {
+        let dir = TestDir::new("zstd-content-size");
+
+        let file_path = dir.create_file("single.zst", &ZSTD_HELLO_FRAME);
+
+        assert_eq!(Some(5), read_zstd_content_size(file_path.as_path()).unwrap());
+
+        let file_path = dir.create_file(
+            "multiple.zst",
+            &[&ZSTD_HELLO_FRAME[..], &ZSTD_SKIPPABLE_FRAME, &ZSTD_RLE_FRAME].concat(),
+        );
+
+        assert_eq!(Some(305), read_zstd_content_size(file_path.as_path()).unwrap());
+
+        // the second frame does not record its size
+        let file_path = dir.create_file(
+            "unknown.zst",
+            &[&ZSTD_HELLO_FRAME[..], &[0x28, 0xB5, 0x2F, 0xFD, 0x00, 0x48, 0x01, 0x00, 0x00]]
+                .concat(),
+        );
+
+        assert_eq!(None, read_zstd_content_size(file_path.as_path()).unwrap());
+
+        let file_path = dir.create_file("not.zst", b"hello, world");
+
+        assert_eq!(None, read_zstd_content_size(file_path.as_path()).unwrap());
+
+        // truncated in a block
+        let file_path = dir.create_file("truncated.zst", &ZSTD_HELLO_FRAME[..8]);
+
+        assert!(read_zstd_content_size(file_path.as_path()).is_err());
+    }
+
+    #[test]
+    fn gzip_uncompressed_size() {
+        let dir = TestDir::new("gzip-uncompressed-size");
+
+        // only the magic number and the trailer are read
+        let mut data = vec![0x1F, 0x8B, 0x08, 0x00];
+
+        data.resize(30, 0);
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&100_000u32.to_le_bytes());
+
+        let file_path = dir.create_file("size.gz", &data);
+
+        assert_eq!(Some(100_000), read_gzip_uncompressed_size(file_path.as_path()).unwrap());
+
+        // a wrapped size is smaller than the compressed data
+        let len = data.len();
+
+        data[len - 4..].copy_from_slice(&10u32.to_le_bytes());
+
+        let file_path = dir.create_file("wrapped.gz", &data);
+
+        assert_eq!(None, read_gzip_uncompressed_size(file_path.as_path()).unwrap());
+
+        let file_path = dir.create_file("not.gz", &[0u8; 30]);
+
+        assert_eq!(None, read_gzip_uncompressed_size(file_path.as_path()).unwrap());
+
+        let file_path = dir.create_file("short.gz", &[0x1F, 0x8B]);
+
+        assert_eq!(None, read_gzip_uncompressed_size(file_path.as_path()).unwrap());
+    }
+
+    #[test]
+    fn unzip_pattern() {
+        assert_eq!("src/a.txt", escape_unzip_pattern("src/a.txt"));
+        assert_eq!("[*]a[?]b[[]c]", escape_unzip_pattern("*a?b[c]"));
+        assert_eq!("é[*]", escape_unzip_pattern("é*"));
+    }
+
+    #[test]
+    fn rar_header_encrypted() {
+        assert!(is_rar_header_encrypted(b"\nSkipping the encrypted archive foo.rar\n"));
+        assert!(is_rar_header_encrypted(b"foo.rar: encrypted headers\n"));
+        assert!(!is_rar_header_encrypted(b"Archive: foo.rar\nDetails: RAR 5\n"));
+    }
+
+    #[test]
+    fn corruption_codes() {
+        assert_eq!(Some(&[1][..]), get_corruption_codes("/usr/bin/gzip"));
+        assert_eq!(Some(&[2][..]), get_corruption_codes("bzip2"));
+        assert_eq!(Some(&[1][..]), get_corruption_codes("unzstd"));
+        assert_eq!(Some(&[2][..]), get_corruption_codes("/usr/bin/bunzip2"));
+        assert_eq!(Some(&[2, 3, 9, 51][..]), get_corruption_codes("unzip"));
+        assert_eq!(None, get_corruption_codes("/opt/bin/my-unzip"));
+    }
+
+    #[test]
+    fn truncated_zip() {
+        let dir = TestDir::new("truncated-zip");
+
+        // a ZIP file with an empty entry `a` stored
+        let mut data = Vec::new();
+
+        data.extend_from_slice(b"PK\x03\x04\x0A\0\0\0\0\0\0\0\x21\0");
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(b"\x01\0\0\0a");
+        data.extend_from_slice(b"PK\x01\x02\x0A\0\x0A\0\0\0\0\0\0\0\x21\0");
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(b"\x01\0");
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(b"a");
+        data.extend_from_slice(b"PK\x05\x06\0\0\0\0\x01\0\x01\0\x2F\0\0\0\x1F\0\0\0\0\0");
+
+        let input_path = dir.create_file("a.zip", data.as_slice());
+
+        Extractor::open(input_path).unwrap().quiet(true).test().unwrap();
+
+        // unzip exits with 9 if the end of central directory record is cut off
+        let input_path = dir.create_file("b.zip", &data[..data.len() - 22]);
+
+        assert!(matches!(
+            Extractor::open(input_path).unwrap().format(ArchiveFormat::Zip).quiet(true).test(),
+            Err(XcompressError::Corrupted)
+        ));
+    }
+
+    #[test]
+    fn rename_extracted_file() {
+        let dir = TestDir::new("rename-extracted-file");
+
+        fs::create_dir_all(dir.join("src/d")).unwrap();
+        fs::write(dir.join("src/d/a.txt"), b"new").unwrap();
+
+        let archive_path = dir.join("a.tar");
+
+        Archiver::new(ArchiveFormat::Tar)
+            .quiet(true)
+            .create(&[dir.join("src/d")], archive_path.as_path())
+            .unwrap();
+
+        fs::create_dir_all(dir.join("output/d")).unwrap();
+        fs::write(dir.join("output/d/a.txt"), b"old").unwrap();
+
+        // the extracted file is renamed rather than the existing one, like the other formats
+        Extractor::open(archive_path)
+            .unwrap()
+            .overwrite(OverwritePolicy::Rename)
+            .quiet(true)
+            .extract_to(dir.join("output"))
+            .unwrap();
+
+        assert_eq!(b"old", fs::read(dir.join("output/d/a.txt")).unwrap().as_slice());
+        assert_eq!(b"new", fs::read(dir.join("output/d/a_1.txt")).unwrap().as_slice());
+        assert_eq!(1, fs::read_dir(dir.join("output")).unwrap().count());
+    }
+
+    #[test]
+    fn overwrite_policy_from_str() {
+        assert_eq!(OverwritePolicy::Never, "never".parse().unwrap());
+        assert_eq!(OverwritePolicy::Ask, "ASK".parse().unwrap());
+        assert!("sometimes".parse::<OverwritePolicy>().is_err());
+    }
+
+    #[test]
+    fn password_with_ask_overwrite() {
+        let dir = TestDir::new("password-with-ask-overwrite");
+
+        let input_path =
+            dir.create_file("a.zip", b"PK\x05\x06\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        let result = Extractor::open(input_path)
+            .unwrap()
+            .format(ArchiveFormat::Zip)
+            .password("secret")
+            .overwrite(OverwritePolicy::Ask)
+            .extract_to(dir.join("output"));
+
+        assert!(matches!(result, Err(XcompressError::InvalidOption(_))));
+        assert!(!dir.join("output").exists());
+    }
+}
@@ -2,4 +2,41 @@
 # XCompress
 
 XCompress is a free file archiver utility on Linux, providing multi-format archiving to and extracting from ZIP, Z, GZIP, BZIP2, LZ, XZ, LZMA, 7ZIP, TAR, RAR and ZSTD.
+
+## Library Usage
+
+The archiving and extracting are done by the external programs, the same as the command-line tool.
+
+```rust,no_run
+use xcompress::{ArchiveFormat, Archiver, CompressionLevel, Extractor};
+
+let report = Archiver::new(ArchiveFormat::TarXz)
+    .level(CompressionLevel::Best)
+    .create(&["src", "Cargo.toml"], "backup.tar.xz")?;
+
+Extractor::open(report.output_path)?.extract_to("restored")?;
+# Ok::<(), xcompress::XcompressError>(())
+```
 */
+
+mod archive_entry;
+mod archive_format;
+mod archiver;
+mod command;
+mod disk_space;
+mod error;
+mod executable_paths;
+mod extractor;
+mod priority;
+mod tar_stream;
+#[cfg(test)]
+mod test_util;
+mod volume;
+
+pub use archive_entry::*;
+pub use archive_format::*;
+pub use archiver::*;
+pub use error::*;
+pub use executable_paths::*;
+pub use extractor::*;
+pub use priority::*;
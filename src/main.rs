@@ -1,24 +1,94 @@
-mod archive_format;
 mod cli;
 mod commands;
 
+use std::process;
+
 use cli::*;
+use xcompress::XcompressError;
 
-fn main() -> anyhow::Result<()> {
+fn main() {
     let args = get_args();
 
-    match &args.command {
+    let quiet = args.quiet;
+
+    let result = match &args.command {
         CLICommands::A {
             ..
-        } => {
-            commands::handle_compression(args)?;
-        },
+        } => commands::handle_compression(args),
         CLICommands::X {
             ..
-        } => {
-            commands::handle_decompression(args)?;
-        },
+        } => commands::handle_decompression(args),
+        CLICommands::L {
+            ..
+        } => commands::handle_list(args),
+        CLICommands::T {
+            ..
+        } => commands::handle_test(args),
+    };
+
+    if let Err(error) = result {
+        let exit_code = match error.downcast_ref::<XcompressError>() {
+            Some(XcompressError::ToolFailed {
+                program,
+                code,
+                signal,
+                ..
+            }) if !quiet => {
+                // the stderr of the program has been printed
+                let error = XcompressError::ToolFailed {
+                    program:     program.clone(),
+                    code:        *code,
+                    signal:      *signal,
+                    stderr_tail: String::new(),
+                };
+
+                eprintln!("Error: {error}");
+
+                get_exit_code(&error)
+            },
+            Some(xcompress_error) => {
+                eprintln!("Error: {error:#}");
+
+                get_exit_code(xcompress_error)
+            },
+            None => {
+                eprintln!("Error: {error:#}");
+
+                EXIT_CODE_GENERAL
+            },
+        };
+
+        process::exit(exit_code);
     }
+}
 
-    Ok(())
+fn get_exit_code(error: &XcompressError) -> i32 {
+    match error {
+        XcompressError::Io {
+            ..
+        } => EXIT_CODE_IO,
+        XcompressError::UnknownFormat
+        | XcompressError::InvalidPath {
+            ..
+        }
+        | XcompressError::MissingVolume(_)
+        | XcompressError::InvalidOption(_) => EXIT_CODE_INVALID_ARGUMENTS,
+        XcompressError::UnsupportedOption {
+            ..
+        } => EXIT_CODE_UNSUPPORTED_OPTION,
+        XcompressError::ToolNotFound(_) => EXIT_CODE_TOOL_NOT_FOUND,
+        XcompressError::ToolFailed {
+            signal, ..
+        } => match signal {
+            Some(signal) => EXIT_CODE_SIGNAL_BASE + signal,
+            None => EXIT_CODE_TOOL_FAILED,
+        },
+        XcompressError::WrongPassword => EXIT_CODE_WRONG_PASSWORD,
+        XcompressError::Corrupted => EXIT_CODE_CORRUPTED,
+        XcompressError::UnsafeEntries(_) => EXIT_CODE_UNSAFE_ENTRIES,
+        XcompressError::LimitExceeded(_) => EXIT_CODE_LIMIT_EXCEEDED,
+        XcompressError::InsufficientSpace {
+            ..
+        } => EXIT_CODE_INSUFFICIENT_SPACE,
+    }
 }
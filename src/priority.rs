@@ -0,0 +1,169 @@
+use std::{io, os::unix::process::CommandExt, process::Command, str::FromStr};
+
+use crate::XcompressError;
+
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+/// The I/O scheduling class and level of the programs, like `ionice`. A lower level means a higher priority, from 0 to 7.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IoPriority {
+    /// Only do I/O when no other program needs the disk.
+    Idle,
+    BestEffort(u8),
+    RealTime(u8),
+}
+
+impl IoPriority {
+    #[inline]
+    fn to_ioprio(self) -> libc::c_int {
+        let (class, level) = match self {
+            IoPriority::RealTime(level) => (1, level),
+            IoPriority::BestEffort(level) => (2, level),
+            IoPriority::Idle => (3, 0),
+        };
+
+        (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level)
+    }
+}
+
+impl FromStr for IoPriority {
+    type Err = XcompressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (s, None),
+        };
+
+        let level = match level {
+            Some(level) => match level.parse::<u8>() {
+                Ok(level) if level <= 7 => level,
+                _ => {
+                    return Err(XcompressError::InvalidOption(format!(
+                        "The I/O priority level should be from 0 to 7, but it is {level:?}."
+                    )));
+                },
+            },
+            None => 4,
+        };
+
+        match class.to_ascii_lowercase().as_str() {
+            "idle" => Ok(IoPriority::Idle),
+            "best-effort" => Ok(IoPriority::BestEffort(level)),
+            "realtime" => Ok(IoPriority::RealTime(level)),
+            _ => Err(XcompressError::InvalidOption(format!(
+                "Unknown I/O scheduling class {class:?}. Accepted values: idle, best-effort, \
+                 realtime."
+            ))),
+        }
+    }
+}
+
+/// Check whether the niceness can be set, before the programs are spawned. Only a privileged process can lower its niceness beyond the limit of `RLIMIT_NICE`, which is usually not at all.
+pub(crate) fn check_nice(nice: Option<i32>) -> Result<(), XcompressError> {
+    let nice = match nice {
+        Some(nice) => nice,
+        None => return Ok(()),
+    };
+
+    if !(-20..=19).contains(&nice) {
+        return Err(XcompressError::InvalidOption(format!(
+            "The niceness should be from -20 to 19, but it is {nice}."
+        )));
+    }
+
+    // -1 is also a valid niceness, so the error is told by errno
+    let current_nice = unsafe {
+        *libc::__errno_location() = 0;
+
+        libc::getpriority(libc::PRIO_PROCESS, 0)
+    };
+
+    if current_nice == -1 && io::Error::last_os_error().raw_os_error() != Some(0) {
+        return Ok(());
+    }
+
+    if nice >= current_nice || unsafe { libc::geteuid() } == 0 {
+        return Ok(());
+    }
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0, rlim_max: 0
+    };
+
+    // the lowest niceness allowed is `20 - rlim_cur`
+    if unsafe { libc::getrlimit(libc::RLIMIT_NICE, &mut limit) } == 0
+        && limit.rlim_cur != libc::RLIM_INFINITY
+        && 20 - nice <= limit.rlim_cur as i32
+    {
+        return Ok(());
+    }
+
+    Err(XcompressError::InvalidOption(format!(
+        "The niceness cannot be lowered from {current_nice} to {nice} without the privilege."
+    )))
+}
+
+/// Make the program run with a niceness and an I/O priority, which are set in the child process before it is executed.
+pub(crate) fn set_priority(command: &mut Command, nice: Option<i32>, ionice: Option<IoPriority>) {
+    if nice.is_none() && ionice.is_none() {
+        return;
+    }
+
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(nice) = nice {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            if let Some(ionice) = ionice {
+                if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ionice.to_ioprio())
+                    != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(IoPriority::Idle, "idle".parse().unwrap());
+        assert_eq!(IoPriority::BestEffort(4), "best-effort".parse().unwrap());
+        assert_eq!(IoPriority::BestEffort(0), "Best-Effort:0".parse().unwrap());
+        assert_eq!(IoPriority::RealTime(7), "realtime:7".parse().unwrap());
+
+        assert!("realtime:8".parse::<IoPriority>().is_err());
+        assert!("best-effort:".parse::<IoPriority>().is_err());
+        assert!("best-effort:-1".parse::<IoPriority>().is_err());
+        assert!("fast".parse::<IoPriority>().is_err());
+    }
+
+    #[test]
+    fn ioprio() {
+        assert_eq!(3 << 13, IoPriority::Idle.to_ioprio());
+        assert_eq!((2 << 13) | 5, IoPriority::BestEffort(5).to_ioprio());
+        assert_eq!(1 << 13, IoPriority::RealTime(0).to_ioprio());
+    }
+
+    #[test]
+    fn nice() {
+        assert!(check_nice(None).is_ok());
+
+        // raising the niceness is always allowed
+        assert!(check_nice(Some(19)).is_ok());
+
+        assert!(matches!(check_nice(Some(20)), Err(XcompressError::InvalidOption(_))));
+        assert!(matches!(check_nice(Some(-21)), Err(XcompressError::InvalidOption(_))));
+    }
+}
@@ -0,0 +1,382 @@
+use crate::{ArchiveEntry, EntryType};
+
+/// The size of a header block and the unit of the data in a TAR stream.
+const BLOCK_SIZE: u64 = 512;
+
+/// The pax extended headers and the GNU long names are buffered to be parsed, so they are limited to be small.
+const MAX_META_DATA_SIZE: u64 = 1024 * 1024;
+
+/// Count the entries and the sizes of the files in a TAR stream while it is piped to tar, so that the extracted data can be limited without watching the output directory. The entries can also be recorded, so that they are checked before tar gets them.
+#[derive(Debug, Default)]
+pub(crate) struct TarStreamCounter {
+    header:                 Vec<u8>,
+    /// The bytes of the data and the padding left for the current entry.
+    remaining:              u64,
+    /// The data of a pax extended header or a GNU long name being read, with its type flag.
+    meta_data:              Option<(u8, Vec<u8>)>,
+    /// The size recorded in the last pax extended header, which overrides the one of the next header.
+    pax_size:               Option<u64>,
+    /// The path and the link target recorded in the last pax extended header or GNU long names, which override the ones of the next header.
+    long_path:              Option<String>,
+    long_link_target:       Option<String>,
+    finished:               bool,
+    record_entries:         bool,
+    pub(crate) entries:     u64,
+    pub(crate) size:        u64,
+    /// The entries parsed since they were taken last time, if they are recorded.
+    pub(crate) new_entries: Vec<ArchiveEntry>,
+}
+
+impl TarStreamCounter {
+    /// Record the entries in `new_entries` besides counting them.
+    #[inline]
+    pub(crate) fn recording_entries() -> TarStreamCounter {
+        TarStreamCounter {
+            record_entries: true,
+            ..TarStreamCounter::default()
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() && !self.finished {
+            if self.remaining > 0 {
+                let length = data.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+
+                if let Some((_, meta_data)) = self.meta_data.as_mut() {
+                    meta_data.extend_from_slice(&data[..length]);
+                }
+
+                self.remaining -= length as u64;
+                data = &data[length..];
+
+                if self.remaining == 0 {
+                    if let Some((type_flag, meta_data)) = self.meta_data.take() {
+                        self.parse_meta_data(type_flag, &meta_data);
+                    }
+                }
+
+                continue;
+            }
+
+            let length = data.len().min(BLOCK_SIZE as usize - self.header.len());
+
+            self.header.extend_from_slice(&data[..length]);
+            data = &data[length..];
+
+            if self.header.len() == BLOCK_SIZE as usize {
+                let header = std::mem::take(&mut self.header);
+
+                self.parse_header(&header);
+            }
+        }
+    }
+
+    fn parse_header(&mut self, header: &[u8]) {
+        // an empty block ends the archive
+        if header.iter().all(|b| *b == 0) {
+            self.finished = true;
+
+            return;
+        }
+
+        let size = match parse_number(&header[124..136]) {
+            Some(size) => size,
+            None => {
+                // not a TAR stream that can be counted, and tar reports it
+                self.finished = true;
+
+                return;
+            },
+        };
+
+        let type_flag = header[156];
+
+        // the size in a pax extended header applies to the next entry rather than another header
+        let size = match type_flag {
+            b'x' | b'g' | b'L' | b'K' => size,
+            _ => self.pax_size.take().unwrap_or(size),
+        };
+
+        self.remaining = size.saturating_add(BLOCK_SIZE - 1) / BLOCK_SIZE * BLOCK_SIZE;
+
+        match type_flag {
+            b'x' | b'L' | b'K' if size <= MAX_META_DATA_SIZE => {
+                self.meta_data = Some((type_flag, Vec::with_capacity(size as usize)));
+
+                return;
+            },
+            // global pax headers, and GNU long names and link names
+            b'x' | b'g' | b'L' | b'K' => return,
+            // directories, links and other special files have no data, and a sparse file has the stored size
+            b'0' | 0 | b'7' | b'S' => {
+                self.entries += 1;
+                self.size += size;
+            },
+            _ => {
+                self.entries += 1;
+            },
+        }
+
+        let long_path = self.long_path.take();
+        let long_link_target = self.long_link_target.take();
+
+        if !self.record_entries {
+            return;
+        }
+
+        let path = long_path.unwrap_or_else(|| {
+            let name = parse_string(&header[..100]);
+
+            // the prefix of the path in the POSIX format
+            if &header[257..263] == b"ustar\0" && header[345] != 0 {
+                format!("{}/{name}", parse_string(&header[345..500]))
+            } else {
+                name
+            }
+        });
+
+        let entry_type = match type_flag {
+            b'0' | 0 | b'7' | b'S' => EntryType::File,
+            b'1' => EntryType::Hardlink,
+            b'2' => EntryType::Symlink,
+            b'5' => EntryType::Directory,
+            _ => EntryType::Other,
+        };
+
+        let mut entry = ArchiveEntry::new(path, entry_type);
+
+        if matches!(entry_type, EntryType::Hardlink | EntryType::Symlink) {
+            entry.link_target =
+                Some(long_link_target.unwrap_or_else(|| parse_string(&header[157..257])));
+        }
+
+        self.new_entries.push(entry);
+    }
+
+    fn parse_meta_data(&mut self, type_flag: u8, meta_data: &[u8]) {
+        // the records and the names end with newlines or zeros, and the data is padded with zeros to the blocks
+        let length = meta_data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        let meta_data = &meta_data[..length];
+
+        match type_flag {
+            b'L' => self.long_path = Some(String::from_utf8_lossy(meta_data).into_owned()),
+            b'K' => self.long_link_target = Some(String::from_utf8_lossy(meta_data).into_owned()),
+            _ => {
+                self.pax_size = parse_pax_size(meta_data);
+
+                if let Some(path) = find_pax_record(meta_data, b"path") {
+                    self.long_path = Some(String::from_utf8_lossy(path).into_owned());
+                }
+
+                if let Some(link_target) = find_pax_record(meta_data, b"linkpath") {
+                    self.long_link_target = Some(String::from_utf8_lossy(link_target).into_owned());
+                }
+            },
+        }
+    }
+}
+
+/// Parse a text field of a TAR header, which ends with a zero unless it is full.
+#[inline]
+fn parse_string(field: &[u8]) -> String {
+    let length = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+
+    String::from_utf8_lossy(&field[..length]).into_owned()
+}
+
+/// Parse a numeric field of a TAR header, which is octal text or a big-endian base-256 number with the highest bit set.
+fn parse_number(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut value = u64::from(field[0] & 0x7F);
+
+        for b in &field[1..] {
+            value = value.checked_mul(256)?.checked_add(u64::from(*b))?;
+        }
+
+        return Some(value);
+    }
+
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+
+    if text.is_empty() {
+        return Some(0);
+    }
+
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Find the `size` record in the data of a pax extended header, whose records are like `16 size=12345678\n`.
+#[inline]
+fn parse_pax_size(meta_data: &[u8]) -> Option<u64> {
+    std::str::from_utf8(find_pax_record(meta_data, b"size")?).ok()?.parse().ok()
+}
+
+/// Find the value of the last record with the keyword in the data of a pax extended header. The records after a malformed one are ignored.
+fn find_pax_record<'a>(meta_data: &'a [u8], keyword: &[u8]) -> Option<&'a [u8]> {
+    let mut rest = meta_data;
+
+    let mut value = None;
+
+    while !rest.is_empty() {
+        let space = match rest.iter().position(|b| *b == b' ') {
+            Some(space) => space,
+            None => return value,
+        };
+
+        let length: usize =
+            match std::str::from_utf8(&rest[..space]).ok().and_then(|length| length.parse().ok()) {
+                Some(length) => length,
+                None => return value,
+            };
+
+        if length <= space || length > rest.len() {
+            return value;
+        }
+
+        let record = &rest[space + 1..length];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+
+        if let Some(v) = record.strip_prefix(keyword).and_then(|v| v.strip_prefix(b"=")) {
+            value = Some(v);
+        }
+
+        rest = &rest[length..];
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a header block with the size in octal.
+    fn create_header(name: &str, type_flag: u8, size: u64) -> Vec<u8> {
+        let mut header = vec![0u8; BLOCK_SIZE as usize];
+
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+        header[156] = type_flag;
+        header[257..263].copy_from_slice(b"ustar\0");
+
+        header
+    }
+
+    /// Append an entry with its data padded to the blocks.
+    fn append_entry(stream: &mut Vec<u8>, name: &str, type_flag: u8, data: &[u8]) {
+        stream.extend(create_header(name, type_flag, data.len() as u64));
+        stream.extend_from_slice(data);
+        stream.resize((stream.len() + 511) / 512 * 512, 0);
+    }
+
+    #[test]
+    fn number() {
+        assert_eq!(Some(0o644), parse_number(b"0000644\0"));
+        assert_eq!(Some(5), parse_number(b"00000000005 "));
+        assert_eq!(Some(0), parse_number(b"\0\0\0\0"));
+        assert_eq!(Some(0x1_0000_0000), parse_number(&[0x80, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]));
+        assert_eq!(None, parse_number(b"12a45678"));
+        assert_eq!(None, parse_number(&[0xFF; 12]));
+    }
+
+    #[test]
+    fn pax_size() {
+        assert_eq!(Some(12345678), parse_pax_size(b"17 size=12345678\n"));
+        assert_eq!(Some(5), parse_pax_size(b"20 path=foo/bar.txt\n10 size=5\n"));
+        assert_eq!(None, parse_pax_size(b"20 path=foo/bar.txt\n"));
+        assert_eq!(None, parse_pax_size(b"99 size=5\n"));
+        assert_eq!(None, parse_pax_size(b"size=5\n"));
+    }
+
+    #[test]
+    fn counter() {
+        let mut stream = Vec::new();
+
+        append_entry(&mut stream, "d/", b'5', b"");
+        append_entry(&mut stream, "d/a.txt", b'0', &[b'a'; 1000]);
+        append_entry(&mut stream, "d/link", b'2', b"");
+        // the size in the pax header overrides the one of the next header
+        append_entry(&mut stream, "PaxHeader", b'x', b"13 size=2048\n");
+        stream.extend(create_header("d/big", b'0', 0));
+        stream.resize(stream.len() + 2048, b'b');
+        append_entry(&mut stream, "d/empty", 0, b"");
+        stream.resize(stream.len() + 1024, 0);
+        // the data after the end is not counted
+        append_entry(&mut stream, "d/ignored", b'0', b"ignored");
+
+        // fed in chunks which are not aligned to the blocks
+        for chunk_size in [1, 100, 512, 4096, stream.len()] {
+            let mut counter = TarStreamCounter::default();
+
+            for chunk in stream.chunks(chunk_size) {
+                counter.update(chunk);
+            }
+
+            assert_eq!(5, counter.entries);
+            assert_eq!(1000 + 2048, counter.size);
+        }
+    }
+
+    #[test]
+    fn recorded_entries() {
+        let mut stream = Vec::new();
+
+        append_entry(&mut stream, "d/a.txt", b'0', b"a");
+
+        let mut header = create_header("link", b'2', 0);
+        header[157..163].copy_from_slice(b"../etc");
+        stream.extend(header);
+
+        // the path is split into the prefix and the name in the POSIX format
+        let mut header = create_header("b.txt", b'0', 0);
+        header[345..346].copy_from_slice(b"d");
+        stream.extend(header);
+
+        append_entry(&mut stream, "././@LongLink", b'L', b"long/name.txt\0");
+        append_entry(&mut stream, "long/name.t", b'0', b"");
+        append_entry(&mut stream, "PaxHeader", b'x', b"18 path=pax/c.txt\n17 linkpath=/etc\n");
+        append_entry(&mut stream, "c", b'1', b"");
+
+        let mut counter = TarStreamCounter::recording_entries();
+
+        for chunk in stream.chunks(100) {
+            counter.update(chunk);
+        }
+
+        let entries: Vec<(&str, EntryType, Option<&str>)> = counter
+            .new_entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.entry_type, entry.link_target.as_deref()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("d/a.txt", EntryType::File, None),
+                ("link", EntryType::Symlink, Some("../etc")),
+                ("d/b.txt", EntryType::File, None),
+                ("long/name.txt", EntryType::File, None),
+                ("pax/c.txt", EntryType::Hardlink, Some("/etc")),
+            ],
+            entries
+        );
+
+        // the entries are not recorded by default
+        let mut counter = TarStreamCounter::default();
+
+        counter.update(&stream);
+
+        assert_eq!(5, counter.entries);
+        assert!(counter.new_entries.is_empty());
+    }
+
+    #[test]
+    fn not_tar() {
+        let mut counter = TarStreamCounter::default();
+
+        counter.update(&[b'x'; 2048]);
+
+        assert_eq!(0, counter.entries);
+        assert_eq!(0, counter.size);
+    }
+}
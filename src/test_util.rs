@@ -0,0 +1,46 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory for the files of a test. It is removed when it is dropped, so it is not left behind by a failed test either.
+pub(crate) struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// The name should be unique among the tests, since they run in parallel.
+    pub(crate) fn new(name: &str) -> TestDir {
+        let path =
+            std::env::temp_dir().join(format!("xcompress-test-{}-{name}", std::process::id()));
+
+        let _ = fs::remove_dir_all(path.as_path());
+
+        fs::create_dir_all(path.as_path()).unwrap();
+
+        TestDir {
+            path,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+
+    /// Write a file in the directory, and return its path.
+    pub(crate) fn create_file(&self, file_name: &str, data: &[u8]) -> PathBuf {
+        let file_path = self.join(file_name);
+
+        fs::write(file_path.as_path(), data).unwrap();
+
+        file_path
+    }
+}
+
+impl Drop for TestDir {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(self.path.as_path());
+    }
+}
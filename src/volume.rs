@@ -0,0 +1,613 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::IoResultExt, ArchiveFormat, XcompressError};
+
+/// The extension of the first volume of a split archive, like `foo.tar.zst.001`.
+pub(crate) const FIRST_VOLUME_EXTENSION: &str = "001";
+
+/// Get the path of the volume with the number, which starts from 1.
+#[inline]
+pub(crate) fn get_volume_path(base_path: &Path, number: usize) -> PathBuf {
+    let mut path = OsString::from(base_path.as_os_str());
+
+    path.push(format!(".{number:03}"));
+
+    PathBuf::from(path)
+}
+
+/// Get the path of the volume following the given one in the naming scheme of a written split archive, like `foo.tar.zst.003` for `foo.tar.zst.002`, `foo.z03` for `foo.z02` or `foo.part3.rar` for `foo.part2.rar`. The volume following `foo.zip` is regarded as `foo.z01`.
+pub(crate) fn get_next_volume_path(volume_path: &Path) -> Option<PathBuf> {
+    let file_name = volume_path.file_name()?.to_str()?;
+
+    let (stem, extension) = file_name.rsplit_once('.')?;
+
+    // foo.7z.001
+    if let Some(number) = parse_volume_number(extension, 3) {
+        return Some(get_volume_path(volume_path.with_extension("").as_path(), number + 1));
+    }
+
+    // foo.z01, foo.zip
+    if let Some(z) = extension.strip_prefix(['z', 'Z']).map(|_| &extension[..1]) {
+        let number = if extension.eq_ignore_ascii_case("zip") {
+            0
+        } else {
+            parse_volume_number(&extension[1..], 2)?
+        };
+
+        return Some(volume_path.with_extension(format!("{z}{:02}", number + 1)));
+    }
+
+    // foo.part1.rar
+    if extension.eq_ignore_ascii_case("rar") {
+        let index = stem.to_ascii_lowercase().rfind(".part")?;
+        let (base, part) = stem.split_at(index);
+
+        let number = parse_volume_number(&part[5..], 1)?;
+        let width = part.len() - 5;
+        let part = &part[..5];
+
+        return Some(
+            volume_path.with_file_name(format!("{base}{part}{:0width$}.{extension}", number + 1)),
+        );
+    }
+
+    None
+}
+
+/// The signature of the end of central directory record of a ZIP file.
+const ZIP_EOCD_SIGNATURE: [u8; 4] = *b"PK\x05\x06";
+
+/// The signature of the ZIP64 end of central directory locator, which is right before the end of central directory record.
+const ZIP64_EOCD_LOCATOR_SIGNATURE: [u8; 4] = *b"PK\x06\x07";
+
+/// The end of central directory record is 22 bytes, followed by a comment of at most 65535 bytes.
+const ZIP_EOCD_MAX_SEARCH_SIZE: u64 = 22 + 65535;
+
+/// Resolve the path of any volume of a split archive to the path which the programs should open, and find all volumes in order. Return an empty `Vec` if the archive is not split.
+///
+/// These naming schemes are recognized.
+///
+/// * `foo.7z.001`, `foo.tar.zst.001`, ...: the first volume is opened.
+/// * `foo.z01`, `foo.z02`, ..., `foo.zip`: the last volume, `foo.zip`, is opened.
+/// * `foo.part1.rar`, `foo.part2.rar`, ...: the first volume is opened.
+/// * `foo.rar`, `foo.r00`, `foo.r01`, ...: the first volume, `foo.rar`, is opened.
+///
+/// The count of the volumes is only recorded by ZIP, so every volume of a split ZIP archive must exist. For the other schemes, the volumes are found until the next one does not exist, and the volumes before the given one must exist.
+pub(crate) fn resolve_volumes(path: &Path) -> Result<(PathBuf, Vec<PathBuf>), XcompressError> {
+    let file_name = match path.file_name().and_then(|file_name| file_name.to_str()) {
+        Some(file_name) => file_name,
+        None => return Ok((path.to_path_buf(), Vec::new())),
+    };
+
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, extension),
+        _ => return Ok((path.to_path_buf(), Vec::new())),
+    };
+
+    // foo.7z.001
+    if let Some(number) = parse_volume_number(extension, 3) {
+        let base_path = path.with_file_name(stem);
+
+        if number == 1
+            || ArchiveFormat::get_archive_format_from_file_path(base_path.as_path()).is_ok()
+            || get_volume_path(base_path.as_path(), 1).is_file()
+        {
+            let volume_paths = collect_volumes(
+                |index| get_volume_path(base_path.as_path(), index + 1),
+                number - 1,
+            )?;
+
+            return Ok((volume_paths[0].clone(), volume_paths));
+        }
+    }
+
+    // foo.z01
+    if let Some(number) = extension.strip_prefix(['z', 'Z']).and_then(|n| parse_volume_number(n, 2))
+    {
+        let zip_path = path.with_extension(if extension.starts_with('Z') { "ZIP" } else { "zip" });
+
+        if !zip_path.is_file() {
+            return Err(XcompressError::MissingVolume(zip_path));
+        }
+
+        let volume_paths = find_zip_volumes(zip_path.as_path())?;
+
+        if volume_paths.len() <= number {
+            return Err(XcompressError::InvalidPath {
+                path:   path.to_path_buf(),
+                reason: "is not a volume of the split ZIP archive",
+            });
+        }
+
+        return Ok((zip_path, volume_paths));
+    }
+
+    if extension.eq_ignore_ascii_case("zip") {
+        return Ok((path.to_path_buf(), find_zip_volumes(path)?));
+    }
+
+    // foo.part1.rar
+    if extension.eq_ignore_ascii_case("rar") {
+        if let Some(index) = stem.to_ascii_lowercase().rfind(".part") {
+            let (base, part) = stem.split_at(index);
+
+            if let Some(number) = parse_volume_number(&part[5..], 1) {
+                let width = part.len() - 5;
+                let part = &part[..5];
+
+                let volume_paths = collect_volumes(
+                    |index| {
+                        path.with_file_name(format!(
+                            "{base}{part}{:0width$}.{extension}",
+                            index + 1
+                        ))
+                    },
+                    number - 1,
+                )?;
+
+                return Ok((volume_paths[0].clone(), volume_paths));
+            }
+        }
+    }
+
+    // foo.rar, foo.r00
+    let old_rar_number = if extension.eq_ignore_ascii_case("rar") {
+        Some(0)
+    } else {
+        extension
+            .strip_prefix(['r', 'R'])
+            .filter(|number| number.len() == 2)
+            .and_then(|number| number.parse::<usize>().ok())
+            .map(|number| number + 1)
+    };
+
+    if let Some(number) = old_rar_number {
+        let rar_extension = if extension.starts_with('R') { "RAR" } else { "rar" };
+        let r = &rar_extension[..1];
+
+        let volume_paths = collect_volumes(
+            |index| match index {
+                0 => path.with_extension(rar_extension),
+                _ => path.with_extension(format!("{r}{:02}", index - 1)),
+            },
+            number,
+        )?;
+
+        if volume_paths.len() > 1 {
+            return Ok((volume_paths[0].clone(), volume_paths));
+        }
+    }
+
+    Ok((path.to_path_buf(), Vec::new()))
+}
+
+/// The volumes of a numbered split archive, like `foo.tar.zst.001`, are cut at the same size except the last one. If the last volume found is as large as the first one, more volumes may follow it, so return the path of the next one, which may be missing.
+pub(crate) fn get_possibly_missing_volume_path(volume_paths: &[PathBuf]) -> Option<PathBuf> {
+    let last_volume_path = volume_paths.last()?;
+
+    let extension = last_volume_path.extension()?.to_str()?;
+
+    parse_volume_number(extension, 3)?;
+
+    let get_size =
+        |volume_path: &PathBuf| volume_path.metadata().ok().map(|metadata| metadata.len());
+
+    if get_size(last_volume_path)? != get_size(&volume_paths[0])? {
+        return None;
+    }
+
+    get_next_volume_path(last_volume_path)
+}
+
+/// Parse the number of a volume, which has at least `min_digits` digits and starts from 1.
+#[inline]
+fn parse_volume_number(s: &str, min_digits: usize) -> Option<usize> {
+    if s.len() < min_digits || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse::<usize>().ok().filter(|number| *number > 0)
+}
+
+/// Collect the volumes from the first one until the next one does not exist. The volume at `given_index` is the one given by the user, so it and the volumes before it must exist.
+fn collect_volumes(
+    get_volume_path: impl Fn(usize) -> PathBuf,
+    given_index: usize,
+) -> Result<Vec<PathBuf>, XcompressError> {
+    let mut volume_paths = Vec::new();
+
+    loop {
+        let volume_path = get_volume_path(volume_paths.len());
+
+        if !volume_path.is_file() {
+            if volume_paths.len() <= given_index {
+                return Err(XcompressError::MissingVolume(volume_path));
+            }
+
+            return Ok(volume_paths);
+        }
+
+        volume_paths.push(volume_path);
+    }
+}
+
+/// The last volume of a split ZIP archive records how many volumes there are, so every volume before it must exist.
+fn find_zip_volumes(zip_path: &Path) -> Result<Vec<PathBuf>, XcompressError> {
+    let last_disk = read_zip_last_disk_number(zip_path).with_path(zip_path)?.unwrap_or(0);
+
+    if last_disk == 0 {
+        return Ok(Vec::new());
+    }
+
+    // the volumes have the same case as the last one, like `FOO.Z01` for `FOO.ZIP`
+    let prefix = if zip_path.extension().unwrap() == "ZIP" { "Z" } else { "z" };
+
+    let mut volume_paths = Vec::with_capacity(last_disk as usize + 1);
+
+    for number in 1..=last_disk {
+        let volume_path = zip_path.with_extension(format!("{prefix}{number:02}"));
+
+        if !volume_path.is_file() {
+            return Err(XcompressError::MissingVolume(volume_path));
+        }
+
+        volume_paths.push(volume_path);
+    }
+
+    volume_paths.push(zip_path.to_path_buf());
+
+    Ok(volume_paths)
+}
+
+/// Read the number of the disk (volume) which has the end of central directory record, starting from 0. Return `None` if the file is not a ZIP file.
+fn read_zip_last_disk_number(zip_path: &Path) -> io::Result<Option<u32>> {
+    let mut file = File::open(zip_path)?;
+
+    let file_size = file.metadata()?.len();
+    let search_size = file_size.min(ZIP_EOCD_MAX_SEARCH_SIZE);
+
+    file.seek(SeekFrom::Start(file_size - search_size))?;
+
+    let mut buffer = Vec::with_capacity(search_size as usize);
+
+    file.read_to_end(&mut buffer)?;
+
+    let eocd_index = match buffer
+        .windows(ZIP_EOCD_SIGNATURE.len())
+        .rposition(|window| window == ZIP_EOCD_SIGNATURE)
+    {
+        Some(index) if index + 22 <= buffer.len() => index,
+        _ => return Ok(None),
+    };
+
+    let disk = u16::from_le_bytes([buffer[eocd_index + 4], buffer[eocd_index + 5]]);
+
+    if disk != u16::MAX {
+        return Ok(Some(u32::from(disk)));
+    }
+
+    // ZIP64 records the total number of disks in the locator
+    match eocd_index.checked_sub(20) {
+        Some(locator_index)
+            if buffer[locator_index..].starts_with(&ZIP64_EOCD_LOCATOR_SIGNATURE) =>
+        {
+            let total_disks = u32::from_le_bytes(
+                buffer[locator_index + 16..locator_index + 20].try_into().unwrap(),
+            );
+
+            Ok(Some(total_disks.saturating_sub(1)))
+        },
+        _ => Ok(Some(u32::from(disk))),
+    }
+}
+
+/// Read the volumes of a split archive one after another, as if they were a single file.
+pub(crate) struct VolumeReader {
+    files:   Vec<File>,
+    current: usize,
+}
+
+impl VolumeReader {
+    /// All volumes are opened at once, so that a missing or unreadable one is reported before the programs start.
+    pub(crate) fn open(volume_paths: &[PathBuf]) -> Result<VolumeReader, XcompressError> {
+        let files = volume_paths
+            .iter()
+            .map(|volume_path| File::open(volume_path).with_path(volume_path))
+            .collect::<Result<Vec<File>, XcompressError>>()?;
+
+        Ok(VolumeReader {
+            files,
+            current: 0,
+        })
+    }
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(file) = self.files.get_mut(self.current) {
+            let c = file.read(buf)?;
+
+            if c > 0 || buf.is_empty() {
+                return Ok(c);
+            }
+
+            self.current += 1;
+        }
+
+        Ok(0)
+    }
+}
+
+/// Write a stream into numbered volumes of a fixed size, `base.001`, `base.002` and so on. The last volume may be smaller, and there is at least one volume.
+pub(crate) struct VolumeWriter {
+    base_path:   PathBuf,
+    volume_size: u64,
+    volumes:     usize,
+    remaining:   u64,
+    file:        File,
+}
+
+impl VolumeWriter {
+    pub(crate) fn create<P: Into<PathBuf>>(
+        base_path: P,
+        volume_size: u64,
+    ) -> Result<VolumeWriter, XcompressError> {
+        let base_path = base_path.into();
+
+        let volume_path = get_volume_path(base_path.as_path(), 1);
+
+        let file = File::create(volume_path.as_path()).with_path(volume_path)?;
+
+        Ok(VolumeWriter {
+            base_path,
+            volume_size,
+            volumes: 1,
+            remaining: volume_size,
+            file,
+        })
+    }
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.remaining == 0 {
+            self.file.flush()?;
+
+            self.volumes += 1;
+            self.file = File::create(get_volume_path(self.base_path.as_path(), self.volumes))?;
+            self.remaining = self.volume_size;
+        }
+
+        let length = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+
+        let c = self.file.write(&buf[..length])?;
+
+        self.remaining -= c as u64;
+
+        Ok(c)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestDir;
+
+    /// An end of central directory record, which says that it is on the disk.
+    fn create_zip_eocd(disk: u16) -> Vec<u8> {
+        let mut eocd = ZIP_EOCD_SIGNATURE.to_vec();
+
+        eocd.extend_from_slice(&disk.to_le_bytes());
+        eocd.resize(22, 0);
+
+        eocd
+    }
+
+    #[test]
+    fn volume_number() {
+        assert_eq!(Some(1), parse_volume_number("001", 3));
+        assert_eq!(Some(1234), parse_volume_number("1234", 3));
+        assert_eq!(Some(2), parse_volume_number("02", 2));
+        assert_eq!(Some(10), parse_volume_number("10", 1));
+        assert_eq!(None, parse_volume_number("01", 3));
+        assert_eq!(None, parse_volume_number("000", 3));
+        assert_eq!(None, parse_volume_number("0a1", 3));
+        assert_eq!(None, parse_volume_number("+01", 2));
+        assert_eq!(None, parse_volume_number("", 1));
+    }
+
+    #[test]
+    fn next_numbered_volume_path() {
+        assert_eq!(
+            Some(PathBuf::from("/a/foo.tar.zst.003")),
+            get_next_volume_path(Path::new("/a/foo.tar.zst.002"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/a/foo.7z.1000")),
+            get_next_volume_path(Path::new("/a/foo.7z.999"))
+        );
+        assert_eq!(None, get_next_volume_path(Path::new("/a/foo.tar.gz")));
+        assert_eq!(None, get_next_volume_path(Path::new("/a/foo")));
+    }
+
+    #[test]
+    fn next_zip_and_rar_volume_path() {
+        assert_eq!(
+            Some(PathBuf::from("/a/foo.z01")),
+            get_next_volume_path(Path::new("/a/foo.zip"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/a/FOO.Z03")),
+            get_next_volume_path(Path::new("/a/FOO.Z02"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/a/foo.part3.rar")),
+            get_next_volume_path(Path::new("/a/foo.part2.rar"))
+        );
+        assert_eq!(
+            Some(PathBuf::from("/a/foo.part010.rar")),
+            get_next_volume_path(Path::new("/a/foo.part009.rar"))
+        );
+        assert_eq!(None, get_next_volume_path(Path::new("/a/foo.rar")));
+    }
+
+    #[test]
+    fn numbered_volumes() {
+        let dir = TestDir::new("numbered-volumes");
+
+        for file_name in
+            ["foo.tar.zst.001", "foo.tar.zst.002", "foo.tar.zst.003", "foo.tar.zst.005"]
+        {
+            dir.create_file(file_name, b"");
+        }
+
+        let expected: Vec<PathBuf> =
+            (1..=3).map(|i| dir.join(format!("foo.tar.zst.{i:03}"))).collect();
+
+        // any volume resolves to the first one, and the volumes stop before the missing one
+        for volume_path in expected.iter() {
+            assert_eq!(
+                (expected[0].clone(), expected.clone()),
+                resolve_volumes(volume_path.as_path()).unwrap()
+            );
+        }
+
+        assert!(matches!(
+            resolve_volumes(dir.join("foo.tar.zst.005").as_path()),
+            Err(XcompressError::MissingVolume(path)) if path == dir.join("foo.tar.zst.004")
+        ));
+    }
+
+    #[test]
+    fn possibly_missing_volume() {
+        let dir = TestDir::new("possibly-missing-volume");
+
+        let volume_paths = vec![
+            dir.create_file("foo.tar.zst.001", b"1234"),
+            dir.create_file("foo.tar.zst.002", b"1234"),
+        ];
+
+        assert_eq!(
+            Some(dir.join("foo.tar.zst.003")),
+            get_possibly_missing_volume_path(&volume_paths)
+        );
+
+        // the last volume is smaller, so it is really the last one
+        let volume_paths = vec![
+            dir.create_file("bar.tar.zst.001", b"1234"),
+            dir.create_file("bar.tar.zst.002", b"12"),
+        ];
+
+        assert_eq!(None, get_possibly_missing_volume_path(&volume_paths));
+
+        let volume_paths = vec![dir.create_file("foo.part1.rar", b"1234")];
+
+        assert_eq!(None, get_possibly_missing_volume_path(&volume_paths));
+        assert_eq!(None, get_possibly_missing_volume_path(&[]));
+    }
+
+    #[test]
+    fn part_volumes() {
+        let dir = TestDir::new("part-volumes");
+
+        for file_name in ["foo.part01.rar", "foo.part02.rar"] {
+            dir.create_file(file_name, b"");
+        }
+
+        let expected = vec![dir.join("foo.part01.rar"), dir.join("foo.part02.rar")];
+
+        assert_eq!(
+            (expected[0].clone(), expected.clone()),
+            resolve_volumes(dir.join("foo.part02.rar").as_path()).unwrap()
+        );
+
+        assert!(matches!(
+            resolve_volumes(dir.join("foo.part03.rar").as_path()),
+            Err(XcompressError::MissingVolume(path)) if path == dir.join("foo.part03.rar")
+        ));
+    }
+
+    #[test]
+    fn old_rar_volumes() {
+        let dir = TestDir::new("old-rar-volumes");
+
+        for file_name in ["foo.rar", "foo.r00", "foo.r01", "bar.rar"] {
+            dir.create_file(file_name, b"");
+        }
+
+        let expected = vec![dir.join("foo.rar"), dir.join("foo.r00"), dir.join("foo.r01")];
+
+        assert_eq!(
+            (expected[0].clone(), expected.clone()),
+            resolve_volumes(dir.join("foo.r01").as_path()).unwrap()
+        );
+
+        // a single RAR file is not split
+        assert_eq!(
+            (dir.join("bar.rar"), Vec::new()),
+            resolve_volumes(dir.join("bar.rar").as_path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn zip_volumes() {
+        let dir = TestDir::new("zip-volumes");
+
+        for file_name in ["foo.z01", "foo.z02", "bar.z01"] {
+            dir.create_file(file_name, b"");
+        }
+
+        dir.create_file("foo.zip", &create_zip_eocd(2));
+        dir.create_file("bar.zip", &create_zip_eocd(2));
+        dir.create_file("baz.zip", &create_zip_eocd(0));
+
+        let expected = vec![dir.join("foo.z01"), dir.join("foo.z02"), dir.join("foo.zip")];
+
+        // the last volume is opened
+        assert_eq!(
+            (expected[2].clone(), expected.clone()),
+            resolve_volumes(dir.join("foo.z01").as_path()).unwrap()
+        );
+        assert_eq!(
+            (expected[2].clone(), expected.clone()),
+            resolve_volumes(dir.join("foo.zip").as_path()).unwrap()
+        );
+
+        assert!(matches!(
+            resolve_volumes(dir.join("bar.zip").as_path()),
+            Err(XcompressError::MissingVolume(path)) if path == dir.join("bar.z02")
+        ));
+
+        assert_eq!(
+            (dir.join("baz.zip"), Vec::new()),
+            resolve_volumes(dir.join("baz.zip").as_path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn not_split() {
+        assert_eq!(
+            (PathBuf::from("/nonexistent/foo.tar.gz"), Vec::new()),
+            resolve_volumes(Path::new("/nonexistent/foo.tar.gz")).unwrap()
+        );
+        assert_eq!(
+            (PathBuf::from("/nonexistent/.001"), Vec::new()),
+            resolve_volumes(Path::new("/nonexistent/.001")).unwrap()
+        );
+    }
+}